@@ -0,0 +1,33 @@
+use crate::error::EngineError;
+use crate::game::GameOver;
+use crate::input::Input;
+
+// TetrisBoard::apply に渡す操作と、その結果
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,  // 1マス左へ
+    MoveRight, // 1マス右へ
+    SoftDrop,  // 1段下げる (ソフトドロップの得点が入る)
+    HardDrop,  // 一番下まで落として固定する
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    Hold,
+    Press(Input),   // キーを押した DAS/ARR・ソフトドロップ・先行回転/ホールドはこちらを使う
+    Release(Input), // キーを離した
+    Tick,           // 1フレーム進める
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepResult {
+    pub error: Option<EngineError>, // 操作できなかった理由 できたなら None
+    pub locked: bool,               // この操作でブロックが固定されたか
+    pub lines: u32,                 // この操作で消したライン数
+    pub game_over: Option<GameOver>,
+}
+impl StepResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
@@ -5,7 +5,8 @@ use rand::{
     Rng,
 };
 
-#[derive(Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum TetrisBlocks {
     NONE,
     WALL,
@@ -18,22 +19,6 @@ pub enum TetrisBlocks {
     L,
     GHOST,
 }
-impl Clone for TetrisBlocks {
-    fn clone(&self) -> Self {
-        match self {
-            TetrisBlocks::NONE => TetrisBlocks::NONE,
-            TetrisBlocks::WALL => TetrisBlocks::WALL,
-            TetrisBlocks::I => TetrisBlocks::I,
-            TetrisBlocks::O => TetrisBlocks::O,
-            TetrisBlocks::S => TetrisBlocks::S,
-            TetrisBlocks::Z => TetrisBlocks::Z,
-            TetrisBlocks::T => TetrisBlocks::T,
-            TetrisBlocks::J => TetrisBlocks::J,
-            TetrisBlocks::L => TetrisBlocks::L,
-            TetrisBlocks::GHOST => TetrisBlocks::GHOST,
-        }
-    }
-}
 impl fmt::Debug for TetrisBlocks {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -81,56 +66,56 @@ pub mod tetris_blocks {
     pub const L: super::BlockColor = 7;
     pub const T: super::BlockColor = 8;
     pub const GHOST: super::BlockColor = 9;
+    pub const GARBAGE: super::BlockColor = 10;
 }
-pub const COLOR_TABLE: [&str; 10] = [
-    "\x1b[48;2;000;000;000m  ", // 何もなし
-    "\x1b[48;2;127;127;127m__", // 壁
-    "\x1b[48;2;000;000;255m__", // I
-    "\x1b[48;2;000;255;000m__", // O
-    "\x1b[48;2;000;255;255m__", // S
-    "\x1b[48;2;255;000;000m__", // Z
-    "\x1b[48;2;255;000;255m__", // J
-    "\x1b[48;2;255;127;000m__", // L
-    "\x1b[48;2;255;255;000m__", // T
-    "\x1b[48;2;000;000;000m[]", // ゴースト
-];
 
 pub type BlockShape = [[usize; 4]; 4];
 pub trait Rotate {
-    fn rotate(&self, _rotate_angle: u8) -> Self;
+    fn rotate(&self, _rotate_angle: u8, _size: usize) -> Self;
 }
+#[allow(clippy::needless_range_loop)]
 impl Rotate for BlockShape {
-    fn rotate(&self, _rotate_angle: u8) -> BlockShape {
+    fn rotate(&self, _rotate_angle: u8, _size: usize) -> BlockShape {
+        //! 左上の _size x _size の範囲で右回りに _rotate_angle 回だけ回転させる
         let mut _r: BlockShape = [[0; 4]; 4];
+        let n = _size - 1;
         match _rotate_angle {
-            0 => {
-                return *self;
-            }
+            0 => *self,
             1 => {
-                for y in 0..4 {
-                    for x in 0..4 {
-                        _r[3 - x][y] = self[y][x];
+                for y in 0.._size {
+                    for x in 0.._size {
+                        _r[x][n - y] = self[y][x];
                     }
                 }
-                return _r;
+                _r
             }
             2 => {
-                for y in 0..4 {
-                    for x in 0..4 {
-                        _r[y][x] = self[3 - y][3 - x];
+                for y in 0.._size {
+                    for x in 0.._size {
+                        _r[y][x] = self[n - y][n - x];
                     }
                 }
-                return _r;
+                _r
             }
             3 => {
-                for y in 0..4 {
-                    for x in 0..4 {
-                        _r[y][x] = self[3 - x][y];
+                for y in 0.._size {
+                    for x in 0.._size {
+                        _r[n - x][y] = self[y][x];
                     }
                 }
-                return _r;
+                _r
             }
-            _ => Self::rotate(self, _rotate_angle % 4),
+            _ => Self::rotate(self, _rotate_angle % 4, _size),
+        }
+    }
+}
+impl TetrisBlocks {
+    pub fn shape(&self, _rotate_angle: u8) -> BlockShape {
+        //! 回転後の形を返す SRSに合わせて I は4x4、O は回転なし、それ以外は3x3の範囲で回す
+        match self {
+            TetrisBlocks::O => BLOCKS[*self as usize],
+            TetrisBlocks::I => BLOCKS[*self as usize].rotate(_rotate_angle, 4),
+            _ => BLOCKS[*self as usize].rotate(_rotate_angle, 3),
         }
     }
 }
@@ -152,50 +137,50 @@ pub const BLOCKS: [BlockShape; 9] = [
     [
         // I
         [0, 0, 0, 0],
-        [0, 0, 0, 0],
         [I, I, I, I],
         [0, 0, 0, 0],
+        [0, 0, 0, 0],
     ],
     [
         // O
-        [0, 0, 0, 0],
         [0, O, O, 0],
         [0, O, O, 0],
         [0, 0, 0, 0],
+        [0, 0, 0, 0],
     ],
     [
         // S
-        [0, 0, 0, 0],
         [0, S, S, 0],
         [S, S, 0, 0],
         [0, 0, 0, 0],
+        [0, 0, 0, 0],
     ],
     [
         // Z
-        [0, 0, 0, 0],
         [Z, Z, 0, 0],
         [0, Z, Z, 0],
         [0, 0, 0, 0],
+        [0, 0, 0, 0],
     ],
     [
         // T
-        [0, 0, 0, 0],
         [0, T, 0, 0],
         [T, T, T, 0],
         [0, 0, 0, 0],
+        [0, 0, 0, 0],
     ],
     [
         // J
-        [0, 0, 0, 0],
         [J, 0, 0, 0],
         [J, J, J, 0],
         [0, 0, 0, 0],
+        [0, 0, 0, 0],
     ],
     [
         // L
-        [0, 0, 0, 0],
         [0, 0, L, 0],
         [L, L, L, 0],
         [0, 0, 0, 0],
+        [0, 0, 0, 0],
     ],
 ];
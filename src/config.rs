@@ -0,0 +1,62 @@
+use crate::game::{BASE_HEIGHT, BASE_WIDTH, BUFFER_HEIGHT};
+use crate::level::GUIDELINE_GRAVITY;
+use crate::randomizer::RandomizerKind;
+use crate::scoring::ScoringRule;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoftDropSpeed {
+    Factor(u32), // 通常の落下速度の何倍で落とすか (SDF)
+    Instant,     // 押した瞬間に一番下まで落とす (固定はしない)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GameConfig {
+    pub width: usize,  // 壁の内側の幅 (4以上)
+    pub height: usize, // 壁の内側の見えている高さ
+    pub buffer_height: usize, // 見えている領域の上の隠れた段数 (2以上)
+    pub randomizer: RandomizerKind,
+    pub seed: Option<u64>, // None なら毎回ランダムなシードを使う
+    pub lock_delay: u64,   // 接地してから固定されるまでの猶予(フレーム)
+    pub lock_resets: u32,  // 接地中に移動・回転で猶予をリセットできる回数
+    pub start_level: u32,
+    pub lines_per_level: u32, // 何ライン消すごとにレベルが上がるか
+    pub gravity_table: &'static [u32], // レベルごとの落下速度 (level.rs 参照)
+    pub scoring: ScoringRule,
+    pub hold_keeps_rotation: bool, // ホールドから戻したときに向きを保つか (false なら出現時の向き)
+    pub das: u64,     // 横移動を押し続けてからリピートが始まるまで(フレーム)
+    pub arr: u64,     // リピートの間隔(フレーム) 0 なら壁まで一気に動く
+    pub das_cut: u64, // 回転した後にリピートを止めておく時間(フレーム)
+    pub soft_drop: SoftDropSpeed,
+    pub are: u64,              // 固定してから次のブロックが出るまでの待ち(フレーム)
+    pub line_clear_delay: u64, // ラインがそろってから消えるまでの待ち(フレーム)
+    pub next_count: usize, // ネクストに見せるブロックの数 (0 なら見せない)
+    pub irs: bool, // 出現時に回転キーが押されていたら回した状態で出す
+    pub ihs: bool, // 出現時にホールドキーが押されていたらすぐにホールドする
+}
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            width: BASE_WIDTH,
+            height: BASE_HEIGHT,
+            buffer_height: BUFFER_HEIGHT,
+            randomizer: RandomizerKind::default(),
+            seed: None,
+            lock_delay: 30,
+            lock_resets: 15,
+            start_level: 1,
+            lines_per_level: 10,
+            gravity_table: &GUIDELINE_GRAVITY,
+            scoring: ScoringRule::default(),
+            hold_keeps_rotation: false,
+            das: 10,
+            arr: 2,
+            das_cut: 0,
+            soft_drop: SoftDropSpeed::Factor(20),
+            are: 0,
+            line_clear_delay: 0,
+            next_count: 3,
+            irs: true,
+            ihs: true,
+        }
+    }
+}
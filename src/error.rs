@@ -0,0 +1,32 @@
+use std::fmt;
+
+use crate::game::GameOver;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineError {
+    GameOver(GameOver), // ゲームオーバーになった、またはすでにゲームオーバー
+    HoldUsed,           // このブロックではもうホールドを使った
+    Blocked,            // 壁やブロックがあって動かせない
+    NoKick,             // 壁蹴りを全部試しても回転できない
+    NoPiece,            // ライン消去・ARE の待ち中で操作できるブロックがない
+    InvalidGarbage,     // せり上がりの穴の列が盤面の外、または messiness が数でない
+}
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineError::GameOver(reason) => write!(f, "game over: {}", reason),
+            EngineError::HoldUsed => write!(f, "hold already used for this piece"),
+            EngineError::Blocked => write!(f, "piece is blocked"),
+            EngineError::NoKick => write!(f, "no rotation kick fits"),
+            EngineError::NoPiece => write!(f, "no active piece"),
+            EngineError::InvalidGarbage => write!(f, "invalid garbage hole or messiness"),
+        }
+    }
+}
+impl std::error::Error for EngineError {}
+
+impl From<GameOver> for EngineError {
+    fn from(reason: GameOver) -> Self {
+        EngineError::GameOver(reason)
+    }
+}
@@ -0,0 +1,46 @@
+use crate::block::TetrisBlocks;
+use crate::game::{GameOver, Position};
+use crate::scoring::TSpin;
+
+// 盤面の中で起きたことを順番に外へ伝えるためのイベント
+// 描画・統計・リプレイ・ボットは TetrisBoard::poll_event / drain_events で受け取る
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    Spawned {
+        block: TetrisBlocks,
+        position: Position,
+        rotate: u8, // 先行回転したときは 0 以外
+    },
+    Moved {
+        position: Position,
+    },
+    Rotated {
+        rotate: u8,
+        position: Position,
+        kick: usize, // 使った壁蹴りテーブルの番号 0 なら蹴りなし
+    },
+    Locked {
+        block: TetrisBlocks,
+        position: Position,
+        rotate: u8,
+        tspin: TSpin,
+    },
+    LinesCleared {
+        lines: u32,
+        tspin: TSpin,
+        perfect_clear: bool,
+        back_to_back: bool, // Back-to-Back の得点が付いたか
+        combo: i32,
+    },
+    Hold {
+        block: TetrisBlocks, // ホールドに入れたブロック
+    },
+    GarbageAdded {
+        rows: usize,
+    },
+    LevelUp {
+        level: u32,
+    },
+    GameOver(GameOver),
+}
@@ -1,26 +1,31 @@
+use std::collections::VecDeque;
 use std::fmt;
 
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::action::{Action, StepResult};
 use crate::block::BlockShape;
-use crate::block::Rotate;
 use crate::block::TetrisBlocks;
-use crate::block::BLOCKS;
-use crate::block::{
-    tetris_blocks::GHOST, tetris_blocks::NONE, tetris_blocks::WALL as W, BlockColor, COLOR_TABLE,
-};
+use crate::block::{tetris_blocks::GARBAGE, tetris_blocks::NONE, tetris_blocks::WALL as W, BlockColor};
+use crate::config::{GameConfig, SoftDropSpeed};
+use crate::error::EngineError;
+use crate::event::GameEvent;
+use crate::input::{Input, InputState};
+use crate::level::{gravity_for, GRAVITY_UNIT, MAX_GRAVITY};
+use crate::randomizer::Randomizer;
+use crate::scoring::TSpin;
+use crate::srs::kick_table;
 
 pub const BASE_WIDTH: usize = 10;
 pub const BASE_HEIGHT: usize = 20;
+pub const BUFFER_HEIGHT: usize = 20; // 見えている領域の上にある隠れた領域の高さ
 pub const EDGE_WIDTH: usize = 3;
-pub const TETRIS_WIDTH: usize = BASE_WIDTH + EDGE_WIDTH * 2;
-pub const TETRIS_HEIGHT: usize = BASE_HEIGHT + EDGE_WIDTH;
-pub const BOARDSIZE: usize = TETRIS_HEIGHT * TETRIS_WIDTH;
-pub const DELTA: u64 = 1000;
-
-pub const LINE_BASE_SCORE: i32 = 100;
+pub const MIN_WIDTH: usize = 4; // Iが横に入る幅
+const GARBAGE_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15; // せり上がり用の乱数をブロック用と別の列にする
 
-pub type Field = [BlockColor; BOARDSIZE];
+pub type Field = Vec<BlockColor>; // 左右と下に EDGE_WIDTH ずつ壁の領域を持つ
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -30,125 +35,321 @@ impl fmt::Debug for Position {
         write!(f, "{{ x : {} ,  y : {} }}", self.x, self.y)
     }
 }
-impl Position {
-    pub fn init() -> Self {
-        Position { x: 4, y: 0 }
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LastAction {
+    #[default]
+    None,
+    Move,
+    Rotate,
+    Rotate180,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Phase {
+    #[default]
+    Falling, // 操作できるブロックがある
+    LineClear(u64), // ライン消去の待ち 残りフレーム数 消える段は clearing_rows
+    Entry(u64),     // 次のブロックが出るまでの待ち(ARE) 残りフレーム数
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOver {
+    BlockOut, // 出現位置が埋まっていてブロックを出せない
+    LockOut,  // ブロックが全部見えている領域より上で固定された
+    TopOut,   // せり上がりで積んだブロックが盤面の上端を越えた
+}
+impl fmt::Display for GameOver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameOver::BlockOut => write!(f, "BLOCK OUT"),
+            GameOver::LockOut => write!(f, "LOCK OUT"),
+            GameOver::TopOut => write!(f, "TOP OUT"),
+        }
     }
 }
 
-#[derive(Clone, Copy)]
 pub struct TetrisBoard {
     pub tetris_board: Field,
+    pub width: usize,  // 壁の内側の幅
+    pub height: usize, // 壁の内側の見えている高さ
+    pub buffer: usize, // 見えている領域の上の隠れた段数 盤面の 0..buffer 段目が隠れた領域
     pub score: i32,
     pub block_placed: i32,
     pub block_rotate: u8,
     pub block_now_shape: TetrisBlocks,
-    pub block_next_three: [TetrisBlocks; 3],
+    pub block_next: VecDeque<TetrisBlocks>, // 次に出てくるブロック 先頭から順に config.next_count 個
     pub block_position: Position,
     pub ghost: Position,
     pub block_hold: TetrisBlocks,
     pub hold_rotate: u8,
+    pub hold_used: bool, // 今のブロックでホールドを使ったか 固定されると戻る
+    pub randomizer: Box<dyn Randomizer>,
+    pub seed: u64,
+    pub config: GameConfig,
+    pub level: u32,
+    pub lines: u32,
+    pub gravity: u32, // 今のレベルの落下速度 (GRAVITY_UNIT = 1G)
+    pub back_to_back: bool, // 直前のライン消去が難しい消し方(テトリスなど)だったか
+    pub combo: i32,         // 連続でラインを消した回数 消していなければ -1
+    pub last_action: LastAction, // 最後に成功した操作 Tスピンの判定に使う
+    pub last_kick: usize,        // 最後の回転で使った壁蹴りテーブルの番号
+    pub last_tspin: TSpin,       // 最後に固定したブロックのTスピン判定
+    pub last_perfect_clear: bool, // 最後のライン消去で盤面が空になったか
+    pub game_over: Option<GameOver>,
+    pub phase: Phase,
+    pub clearing_rows: Vec<usize>, // ライン消去の待ち中に消える段
+    pub frame: u64, // 始まってから進めたフレーム数
+    pub input: InputState,
+    pub lock_timer: Option<u64>, // 接地してからの経過フレーム数 未接地なら None
+    pub lock_resets: u32,
+    lowest_y: usize,
+    fall_progress: u64, // 落下の端数 GRAVITY_UNIT で1段
+    garbage_hole: Option<usize>, // 最後に入れたせり上がりの穴の列
+    events: VecDeque<GameEvent>, // まだ取り出されていないイベント
+    rng: StdRng,         // ブロックの順番を決める乱数
+    garbage_rng: StdRng, // せり上がりの穴を決める乱数 せり上がりを受けてもブロックの順番は変わらない
 }
 impl fmt::Debug for TetrisBoard {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Score:{}. #of block placed:{}. Rotate_angle:{}.\nNext 3 blocks:{:?},{:?},{:?}.\nHold block:{:?}",
-            self.score, self.block_placed, self.block_rotate,self.block_next_three[0],self.block_next_three[1],self.block_next_three[2],self.block_hold,
-        )
+            "Score:{}. Level:{}. Lines:{}. #of block placed:{}. Rotate_angle:{}.\nNext {} blocks:{:?}.\nHold block:{:?}",
+            self.score, self.level, self.lines, self.block_placed, self.block_rotate,self.block_next.len(),self.block_next,self.block_hold,
+        )?;
+        if let Some(reason) = self.game_over {
+            write!(f, "\nGame over:{}", reason)?;
+        }
+        Ok(())
     }
 }
 
 // TODO: ブロックの色付け?
-// TODO:　ブロックのオフセット 回転の時
-// TODO:　ホールド
-// TODO:　次のブロックの表示
 
-#[allow(clippy::needless_return)]
+impl Default for TetrisBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[allow(clippy::needless_return, clippy::needless_range_loop)]
 impl TetrisBoard {
     pub fn new() -> Self {
-        let mut _b = Self::init_board();
-        let mut _r: u8 = 0;
-        let mut _p: Position = Position::init();
-        let _blockshape = rand::random::<TetrisBlocks>();
-        let mut _next_blocks: [TetrisBlocks; 3] = [TetrisBlocks::I; 3];
-        for _i in 0..3 {
-            _next_blocks[_i] = rand::random::<TetrisBlocks>();
-        }
-        TetrisBoard {
-            tetris_board: _b, // うえから0,1,2かな高さは
+        Self::with_config(GameConfig::default())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        //! シードを固定して盤面を作る 同じシードと同じ操作なら同じ盤面になる
+        Self::with_config(GameConfig {
+            seed: Some(seed),
+            ..GameConfig::default()
+        })
+    }
+
+    pub fn with_config(config: GameConfig) -> Self {
+        //! 設定を指定して盤面を作る
+        let _width = config.width.max(MIN_WIDTH);
+        let _height = config.height.max(1);
+        let _buffer = config.buffer_height.max(2); // 出現位置の2段は必ず確保する
+        let mut _randomizer: Box<dyn Randomizer> = config.randomizer.build();
+        let _seed = config.seed.unwrap_or_else(rand::random);
+        let mut _rng = StdRng::seed_from_u64(_seed);
+        let _blockshape = _randomizer.next(&mut _rng);
+        let _next_blocks: VecDeque<TetrisBlocks> =
+            (0..config.next_count).map(|_| _randomizer.next(&mut _rng)).collect();
+        let mut _board = TetrisBoard {
+            tetris_board: Self::init_board(_width, _buffer + _height), // うえから0,1,2かな高さは
+            width: _width,
+            height: _height,
+            buffer: _buffer,
             score: 0,
             block_placed: 0,
-            block_rotate: _r,
+            block_rotate: 0,
             block_now_shape: _blockshape,
-            block_position: _p,
-            block_next_three: _next_blocks,
-            ghost: Self::calc_init_ghost(_b, _r, _p, _blockshape),
+            block_position: Position { x: 0, y: 0 },
+            block_next: _next_blocks,
+            ghost: Position { x: 0, y: 0 },
             block_hold: TetrisBlocks::NONE,
             hold_rotate: 0,
-        }
+            hold_used: false,
+            randomizer: _randomizer,
+            seed: _seed,
+            config,
+            level: config.start_level.max(1),
+            lines: 0,
+            gravity: gravity_for(config.gravity_table, config.start_level),
+            back_to_back: false,
+            combo: -1,
+            last_action: LastAction::None,
+            last_kick: 0,
+            last_tspin: TSpin::None,
+            last_perfect_clear: false,
+            game_over: None,
+            phase: Phase::Falling,
+            clearing_rows: Vec::new(),
+            frame: 0,
+            input: InputState::default(),
+            lock_timer: None,
+            lock_resets: 0,
+            lowest_y: 0,
+            fall_progress: 0,
+            garbage_hole: None,
+            events: VecDeque::new(),
+            rng: _rng,
+            garbage_rng: StdRng::seed_from_u64(_seed ^ GARBAGE_SEED_SALT),
+        };
+        _board.block_position = _board.spawn_position();
+        _board.ghost_pos();
+        _board.reset_piece_state();
+        _board.emit_spawned();
+        _board
     }
 
-    pub fn calc_init_ghost(
-        _board: Field,
-        _rotate: u8,
-        _pos: Position,
-        _blks: TetrisBlocks,
-    ) -> Position {
-        let mut new_pos: Position;
-        let mut _tmp = 0;
-        let _b: BlockShape = BLOCKS[_blks as usize].rotate(_rotate);
-        loop {
-            new_pos = Position {
-                x: _pos.x,
-                y: _pos.y + _tmp,
-            };
-            let mut _result: bool = false;
-            for y in 0..4 {
-                for x in 0..4 {
-                    if y + new_pos.y + 1 >= TETRIS_HEIGHT || x + new_pos.x >= TETRIS_WIDTH {
-                        continue;
-                    }
-                    if _board[(y + new_pos.y + 1) * TETRIS_WIDTH + x + new_pos.x] != NONE
-                        && _b[y][x] != NONE
-                    {
-                        _result = true;
-                    }
-                }
+    pub fn apply(&mut self, action: Action) -> StepResult {
+        //! 操作を1つ行う 固定・ライン消去・次のブロックの出現はこの中で順番に処理する
+        let _start = self.events.len();
+        let _result = match action {
+            Action::MoveLeft => self.move_left(1),
+            Action::MoveRight => self.move_right(1),
+            Action::SoftDrop => self.move_down(1),
+            Action::HardDrop => self.hard_drop(),
+            Action::RotateCw => self.rotate_cw(),
+            Action::RotateCcw => self.rotate_ccw(),
+            Action::Rotate180 => self.rotate_180(),
+            Action::Hold => self.hold_block(),
+            Action::Press(input) => self.press(input),
+            Action::Release(input) => {
+                self.release(input);
+                Ok(())
             }
-            if _result {
-                break;
-            } else {
-                _tmp += 1;
+            Action::Tick => self.tick(),
+        };
+        let mut _step = StepResult {
+            error: _result.err(),
+            game_over: self.game_over,
+            ..Default::default()
+        };
+        for _event in self.events.range(_start..) {
+            match _event {
+                GameEvent::Locked { .. } => _step.locked = true,
+                GameEvent::LinesCleared { lines, .. } => _step.lines += lines,
+                _ => (),
             }
         }
-        return new_pos;
+        _step
     }
 
-    pub fn rotate(&mut self) {
-        //! 回転させる関数 基本的に左回り
-        self.block_rotate += 1;
-        self.block_rotate %= 4;
+    pub fn poll_event(&mut self) -> Option<GameEvent> {
+        //! 一番古いイベントを1つ取り出す
+        self.events.pop_front()
     }
 
-    pub fn rotate_undo(&mut self) {
-        //! 回転を戻す
-        self.block_rotate += 3;
-        self.block_rotate %= 4;
+    pub fn drain_events(&mut self) -> impl Iterator<Item = GameEvent> + '_ {
+        //! たまっているイベントを古い順に全部取り出す
+        self.events.drain(..)
     }
 
-    pub fn check_rotate(&mut self){
-        //! 回転可能か確かめる
-        let new_pos = Position {
-            x: self.block_position.x,
-            y: self.block_position.y,
-        };
-        if !TetrisBoard::is_collision(&self, &new_pos) {
-            self.block_position = new_pos;
-        } else if let Ok(new_pos) = TetrisBoard::super_rotation(&self) {
-            self.block_position = new_pos;
-        } else {
-            self.rotate_undo();
+    fn emit(&mut self, event: GameEvent) {
+        self.events.push_back(event);
+    }
+
+    fn emit_spawned(&mut self) {
+        self.emit(GameEvent::Spawned {
+            block: self.block_now_shape,
+            position: self.block_position,
+            rotate: self.block_rotate,
+        });
+    }
+
+    pub fn tetris_width(&self) -> usize {
+        //! 壁の領域を含めた盤面の幅
+        self.width + EDGE_WIDTH * 2
+    }
+
+    pub fn tetris_height(&self) -> usize {
+        //! 壁の領域と隠れた領域を含めた盤面の高さ
+        self.field_height() + EDGE_WIDTH
+    }
+
+    pub fn field_height(&self) -> usize {
+        //! 壁の内側の高さ 隠れた領域も含む
+        self.buffer + self.height
+    }
+
+    pub fn spawn_position(&self) -> Position {
+        //! ブロックの出現位置 3マス幅のブロックが中央(左寄り)に来るようにする
+        //! 高さは見えている領域のすぐ上の2段 (下から数えて21, 22段目)
+        Position {
+            x: EDGE_WIDTH + (self.width - 3) / 2,
+            y: self.buffer - 2,
+        }
+    }
+
+    pub fn rotate_cw(&mut self) -> Result<(), EngineError> {
+        //! 右回りに回転させる
+        self.turn(1)
+    }
+
+    pub fn rotate_ccw(&mut self) -> Result<(), EngineError> {
+        //! 左回りに回転させる
+        self.turn(3)
+    }
+
+    pub fn rotate_180(&mut self) -> Result<(), EngineError> {
+        //! 180度回転させる
+        self.turn(2)
+    }
+
+    fn turn(&mut self, quarter: u8) -> Result<(), EngineError> {
+        //! 右回りに quarter 回だけ回して壁蹴りを確かめる
+        self.check_active()?;
+        let from = self.block_rotate;
+        self.block_rotate = (from + quarter) % 4;
+        self.check_rotate(from)
+    }
+
+    fn check_rotate(&mut self, from: u8) -> Result<(), EngineError> {
+        //! 回転可能か確かめる 壁蹴りでも置けなければ from の向きに戻す
+        match self.super_rotation(from) {
+            Ok((new_pos, kick)) => {
+                self.block_position = new_pos;
+                self.last_action = if (from + 2) % 4 == self.block_rotate {
+                    LastAction::Rotate180
+                } else {
+                    LastAction::Rotate
+                };
+                self.last_kick = kick;
+                self.input.das_cut_timer = self.config.das_cut;
+                self.reset_lock();
+                self.ghost_pos();
+                self.emit(GameEvent::Rotated {
+                    rotate: self.block_rotate,
+                    position: new_pos,
+                    kick,
+                });
+                Ok(())
+            }
+            Err(e) => {
+                self.block_rotate = from;
+                Err(e)
+            }
+        }
+    }
+
+    fn check_alive(&self) -> Result<(), EngineError> {
+        //! ゲームオーバー後の操作はエラーにする
+        match self.game_over {
+            Some(reason) => Err(EngineError::GameOver(reason)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_active(&self) -> Result<(), EngineError> {
+        //! ブロックを動かす操作の前に確かめる ライン消去・ARE の待ち中はエラーにする
+        self.check_alive()?;
+        match self.phase {
+            Phase::Falling => Ok(()),
+            _ => Err(EngineError::NoPiece),
         }
     }
     pub fn add_score(&mut self, pts: i32) {
@@ -156,13 +357,15 @@ impl TetrisBoard {
         self.score += pts;
     }
 
-    pub fn init_board() -> Field {
-        //! ボードの初期化
-        let mut _b: Field = [NONE; BOARDSIZE];
-        for i in 0..BOARDSIZE {
-            if i % TETRIS_WIDTH == EDGE_WIDTH - 1
-                || i % TETRIS_WIDTH == TETRIS_WIDTH - EDGE_WIDTH
-                || i / TETRIS_WIDTH == TETRIS_HEIGHT - EDGE_WIDTH
+    fn init_board(width: usize, height: usize) -> Field {
+        //! ボードの初期化 width, height は壁の内側の大きさ (height は隠れた領域も含む)
+        let _tetris_width = width + EDGE_WIDTH * 2;
+        let _size = _tetris_width * (height + EDGE_WIDTH);
+        let mut _b: Field = vec![NONE; _size];
+        for i in 0.._size {
+            if i % _tetris_width == EDGE_WIDTH - 1
+                || i % _tetris_width == _tetris_width - EDGE_WIDTH
+                || i / _tetris_width == height
             {
                 _b[i] = W; // 壁の領域に壁を設置する
             } else {
@@ -171,112 +374,324 @@ impl TetrisBoard {
         }
         return _b;
     }
-    pub fn hard_drop(&mut self){
-        //! ハードドロップさせる
+    pub fn hard_drop(&mut self) -> Result<(), EngineError> {
+        //! ハードドロップさせる 猶予なしでその場で固定する
+        self.check_active()?;
         let mut _tmp = 0;
-                let mut new_pos: Position;
-                loop {
-                    new_pos = Position {
-                        x: self.block_position.x,
-                        y: self.block_position.y + _tmp,
-                    };
-                    if TetrisBoard::is_collision(
-                        &self,
-                        &Position {
-                            x: self.block_position.x,
-                            y: new_pos.y + 1,
-                        },
-                    ) {
-                        break;
-                    }
-                    _tmp += 1;
-                }
-                self.block_position = new_pos;
-                self.add_score(_tmp as i32);
+        let mut new_pos: Position;
+        loop {
+            new_pos = Position {
+                x: self.block_position.x,
+                y: self.block_position.y + _tmp,
+            };
+            if TetrisBoard::is_collision(
+                self,
+                &Position {
+                    x: self.block_position.x,
+                    y: new_pos.y + 1,
+                },
+            ) {
+                break;
+            }
+            _tmp += 1;
+        }
+        self.block_position = new_pos;
+        if _tmp > 0 {
+            self.last_action = LastAction::Move;
+            self.emit(GameEvent::Moved { position: new_pos });
+        }
+        self.add_score(self.config.scoring.hard_drop(_tmp as u32));
+        self.lock_piece()
     }
 
-    pub fn move_left(&mut self,diff: usize){
+    pub fn move_left(&mut self, diff: usize) -> Result<(), EngineError> {
         let new_pos = Position {
             x: self
                 .block_position
                 .x
-                .checked_sub(diff)
-                .unwrap_or_else(|| self.block_position.x), // 符号なしでマイナスにならないようにする
+                .checked_sub(diff) // 符号なしでマイナスにならないようにする
+                .ok_or(EngineError::Blocked)?,
             y: self.block_position.y,
         };
-        if !TetrisBoard::is_collision(&self, &new_pos) {
-            self.block_position = new_pos;
-        }
+        self.shift(new_pos)
     }
 
-    pub fn move_right(&mut self,diff: usize){
+    pub fn move_right(&mut self, diff: usize) -> Result<(), EngineError> {
         let new_pos = Position {
-            x: self
-                .block_position
-                .x
-                .checked_add(diff)
-                .unwrap_or_else(|| self.block_position.x), 
+            x: self.block_position.x + diff,
             y: self.block_position.y,
         };
-        if !TetrisBoard::is_collision(&self, &new_pos) {
-            self.block_position = new_pos;
-        }
+        self.shift(new_pos)
     }
 
-    pub fn move_down(&mut self,diff: usize){
+    pub fn move_down(&mut self, diff: usize) -> Result<(), EngineError> {
+        //! ソフトドロップ 落とした段数に応じて得点が入る
         let new_pos = Position {
             x: self.block_position.x,
             y: self.block_position.y + diff,
         };
-        if !TetrisBoard::is_collision(&self, &new_pos) {
-            self.block_position = new_pos;
+        self.shift(new_pos)?;
+        self.add_score(self.config.scoring.soft_drop(diff as u32));
+        Ok(())
+    }
+
+    fn shift(&mut self, new_pos: Position) -> Result<(), EngineError> {
+        //! new_pos に動かせるなら動かす
+        self.check_active()?;
+        if self.is_collision(&new_pos) {
+            return Err(EngineError::Blocked);
+        }
+        self.block_position = new_pos;
+        self.last_action = LastAction::Move;
+        self.reset_lock();
+        self.ghost_pos();
+        self.emit(GameEvent::Moved { position: new_pos });
+        Ok(())
+    }
+
+    fn fall(&mut self) -> bool {
+        //! 重力で1段落とす 得点は入らない 落とせたかを返す
+        let new_pos = Position {
+            x: self.block_position.x,
+            y: self.block_position.y + 1,
+        };
+        if self.is_collision(&new_pos) {
+            return false;
         }
+        self.block_position = new_pos;
+        self.last_action = LastAction::Move;
+        self.reset_lock();
+        self.emit(GameEvent::Moved { position: new_pos });
+        true
     }
 
-    pub fn debug_draw(&self) {
-        //! 盤面を描画する関数 Debug情報込みで余計なものも多い
-        
-        let mut field_buffer = self.tetris_board.clone();
-        let _b: BlockShape = BLOCKS[self.block_now_shape as usize].rotate(self.block_rotate);
-        let mut _x: usize; // 一次的変数
-        let mut _y: usize; // 一次的変数
-        let mut _board_position: usize; // 一次的変数
-        let _g = self.ghost;
-        for y in 0..4 {
-            for x in 0..4 {
-                if _b[y][x] != NONE {
-                    _x = x + self.block_position.x;
-                    _y = y + self.block_position.y;
-                    _board_position = _y * TETRIS_WIDTH + _x;
-                    field_buffer[_board_position] = _b[y][x];
-                    field_buffer[(_g.y + y) * TETRIS_WIDTH + _g.x + x] = GHOST;
+    pub fn tick(&mut self) -> Result<(), EngineError> {
+        //! 1フレーム(1/60秒)進める 横移動のリピート、自由落下、接地後の固定猶予を処理する
+        //! 時間はすべてフレーム数で数えるので、同じ入力を同じフレームに入れれば同じ結果になる
+        self.check_alive()?;
+        self.frame += 1;
+        match self.phase {
+            Phase::Falling => (),
+            Phase::LineClear(n) | Phase::Entry(n) if n > 1 => {
+                // 待ちの間もキーは押されたままなので DAS は溜めておく
+                if self.input.shift.is_some() {
+                    self.input.das_timer += 1;
                 }
+                self.phase = match self.phase {
+                    Phase::LineClear(_) => Phase::LineClear(n - 1),
+                    _ => Phase::Entry(n - 1),
+                };
+                return Ok(());
             }
+            Phase::LineClear(_) => return self.finish_lock(),
+            Phase::Entry(_) => {
+                self.phase = Phase::Falling;
+                return self.next_block();
+            }
+        }
+        self.auto_shift();
+        self.apply_gravity();
+        if !self.is_grounded() {
+            return Ok(());
+        }
+        let _timer = self.lock_timer.map_or(1, |t| t + 1); // 接地した最初のフレームを1と数える
+        self.lock_timer = Some(_timer);
+        if _timer >= self.config.lock_delay {
+            return self.lock_piece();
         }
-        println!("\x1b[2J\x1b[H\x1b[?25l");
-        for i in 0..=TETRIS_HEIGHT - EDGE_WIDTH {
-            //y
-            for j in EDGE_WIDTH - 1..=TETRIS_WIDTH - EDGE_WIDTH {
-                print!("{}", COLOR_TABLE[field_buffer[i * TETRIS_WIDTH + j]]);
+        Ok(())
+    }
+
+    fn apply_gravity(&mut self) {
+        //! 1フレームぶん落下させる ソフトドロップ中は速く落とし、落とした段数の得点を入れる
+        const ROW: u64 = GRAVITY_UNIT as u64;
+        let _soft = self.input.soft_drop;
+        let _gravity = match (_soft, self.config.soft_drop) {
+            (true, SoftDropSpeed::Factor(f)) => (self.gravity as u64 * f as u64).min(MAX_GRAVITY as u64),
+            (true, SoftDropSpeed::Instant) => u64::MAX,
+            (false, _) => self.gravity as u64,
+        };
+        let mut _rows = 0;
+        if _gravity == u64::MAX {
+            while self.fall() {
+                _rows += 1;
             }
-            print!("\x1b[49;0;0;0m "); // リセット
-            println!(); // 改行
+            self.fall_progress = 0;
+        } else {
+            self.fall_progress += _gravity;
+            while self.fall_progress >= ROW {
+                self.fall_progress -= ROW;
+                if !self.fall() {
+                    self.fall_progress = 0;
+                    break;
+                }
+                _rows += 1;
+            }
+        }
+        if _soft {
+            self.add_score(self.config.scoring.soft_drop(_rows));
+        }
+    }
+
+    pub fn press(&mut self, input: Input) -> Result<(), EngineError> {
+        //! キーを押した 横移動はすぐに1マス動かして DAS を溜め始める
+        //! ソフトドロップは押している間 update での落下が速くなる
+        //! 回転・ホールドはその場で行い、押したままなら次のブロックの先行回転・先行ホールドになる
+        self.check_alive()?;
+        if self.input.is_held(input) {
+            return Ok(());
+        }
+        self.input.set_held(input, true);
+        match input {
+            Input::Left | Input::Right => {
+                self.input.start_shift(Some(input));
+                let _ = self.shift_once(input);
+                Ok(())
+            }
+            Input::SoftDrop => Ok(()),
+            Input::RotateCw => self.rotate_cw(),
+            Input::RotateCcw => self.rotate_ccw(),
+            Input::Rotate180 => self.rotate_180(),
+            Input::Hold => self.hold_block(),
+        }
+    }
+
+    pub fn release(&mut self, input: Input) {
+        //! キーを離した 反対側が押されたままならそちらのリピートに切り替える
+        self.input.set_held(input, false);
+        if self.input.shift == Some(input) {
+            let _other = if input == Input::Left {
+                Input::Right
+            } else {
+                Input::Left
+            };
+            self.input
+                .start_shift(Some(_other).filter(|&i| self.input.is_held(i)));
+        }
+    }
+
+    fn shift_once(&mut self, input: Input) -> Result<(), EngineError> {
+        match input {
+            Input::Left => self.move_left(1),
+            Input::Right => self.move_right(1),
+            _ => Err(EngineError::Blocked), // 横移動ではない
+        }
+    }
+
+    fn auto_shift(&mut self) {
+        //! 押し続けている方向に DAS/ARR に従って動かす
+        let Some(_dir) = self.input.shift else {
+            return;
+        };
+        let _before = self.input.das_timer;
+        self.input.das_timer += 1;
+        if self.input.das_cut_timer > 0 {
+            self.input.das_cut_timer -= 1;
+            return;
+        }
+        if self.input.das_timer < self.config.das {
+            return;
+        }
+        if _before < self.config.das {
+            // DAS が溜まった瞬間に1マス動かし、残りの時間はリピートに回す
+            self.input.arr_timer = self.input.das_timer - self.config.das;
+            let _ = self.shift_once(_dir);
+        } else {
+            self.input.arr_timer += 1;
+        }
+        if self.config.arr == 0 {
+            while self.shift_once(_dir).is_ok() {} // 壁まで一気に動かす
+            return;
+        }
+        while self.input.arr_timer >= self.config.arr {
+            self.input.arr_timer -= self.config.arr;
+            if self.shift_once(_dir).is_err() {
+                self.input.arr_timer = 0;
+                break;
+            }
+        }
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        //! 1つ下に動けないか
+        self.is_collision(&Position {
+            x: self.block_position.x,
+            y: self.block_position.y + 1,
+        })
+    }
+
+    fn reset_lock(&mut self) {
+        //! 移動・回転に成功したときの固定猶予のリセット 最下段を更新したら回数も戻す
+        if self.block_position.y > self.lowest_y {
+            self.lowest_y = self.block_position.y;
+            self.lock_timer = None;
+            self.lock_resets = 0;
+        } else if self.lock_timer.is_some() && self.lock_resets < self.config.lock_resets {
+            self.lock_timer = Some(0);
+            self.lock_resets += 1;
+        }
+    }
+
+    fn reset_piece_state(&mut self) {
+        //! 新しいブロックが出てきたときに落下・固定の状態を初期化する
+        self.lock_timer = None;
+        self.lock_resets = 0;
+        self.lowest_y = self.block_position.y;
+        self.fall_progress = 0;
+        self.last_action = LastAction::None;
+        self.last_kick = 0;
+    }
+
+    fn lock_piece(&mut self) -> Result<(), EngineError> {
+        //! ブロックを固定してラインを消し、次のブロックを出す
+        //! 全部が見えている領域より上で固定されたらロックアウト
+        let _lock_out = self.block_position.y + self.lowest_cell_row() < self.buffer;
+        self.block_fixing();
+        if _lock_out {
+            return self.end_game(GameOver::LockOut);
         }
-        println!("Now Block is {:?}", self.block_now_shape);
-        println!("{:?}", self);
-        println!("Now Pointing area is {:?}", self.block_position);
-        print!("\x1b[49;0;0;0m "); // リセット
+        self.clearing_rows = self.full_rows();
+        if !self.clearing_rows.is_empty() && self.config.line_clear_delay > 0 {
+            self.phase = Phase::LineClear(self.config.line_clear_delay);
+            return Ok(());
+        }
+        self.finish_lock()
+    }
+
+    fn finish_lock(&mut self) -> Result<(), EngineError> {
+        //! ラインを消し、ARE があれば待ちに入る なければすぐ次のブロックを出す
+        self.erase_lines();
+        self.clearing_rows.clear();
+        if self.config.are > 0 {
+            self.phase = Phase::Entry(self.config.are);
+            return Ok(());
+        }
+        self.phase = Phase::Falling;
+        self.next_block()
+    }
+
+    fn lowest_cell_row(&self) -> usize {
+        //! ブロックの形の中で一番下にあるマスの段 (4x4 の中での位置)
+        let _b: BlockShape = self.block_now_shape.shape(self.block_rotate);
+        (0..4).rev().find(|&y| _b[y].iter().any(|&c| c != NONE)).unwrap_or(0)
+    }
+
+    fn end_game(&mut self, reason: GameOver) -> Result<(), EngineError> {
+        //! ゲームオーバーにする
+        self.game_over = Some(reason);
+        self.emit(GameEvent::GameOver(reason));
+        Err(EngineError::GameOver(reason))
     }
 
     pub fn is_collision(&self, pos: &Position) -> bool {
         //! 当たり判定の関数
-        let _b: BlockShape = BLOCKS[self.block_now_shape as usize].rotate(self.block_rotate);
+        let _b: BlockShape = self.block_now_shape.shape(self.block_rotate);
         for y in 0..4 {
             for x in 0..4 {
-                if y + pos.y >= TETRIS_HEIGHT || x + pos.x >= TETRIS_WIDTH {
+                if y + pos.y >= self.tetris_height() || x + pos.x >= self.tetris_width() {
                     continue;
                 }
-                if self.tetris_board[(y + pos.y) * TETRIS_WIDTH + x + pos.x] != NONE
+                if self.tetris_board[(y + pos.y) * self.tetris_width() + x + pos.x] != NONE
                     && _b[y][x] != NONE
                 {
                     return true;
@@ -286,7 +701,7 @@ impl TetrisBoard {
         return false;
     }
 
-    pub fn ghost_pos(&mut self) {
+    fn ghost_pos(&mut self) {
         //! ゴーストの座標を返す
         let mut new_pos: Position;
         let mut _tmp = 0;
@@ -296,7 +711,7 @@ impl TetrisBoard {
                 y: self.block_position.y + _tmp,
             };
             if TetrisBoard::is_collision(
-                &self,
+                self,
                 &Position {
                     x: self.block_position.x,
                     y: new_pos.y + 1,
@@ -309,162 +724,746 @@ impl TetrisBoard {
         self.ghost = new_pos;
     }
 
-    pub fn erase_lines(&mut self) {
+    fn erase_lines(&mut self) {
         //! ラインを消去する関数
-        let mut erase_lines = 0;
-        for y in 1..TETRIS_HEIGHT - EDGE_WIDTH {
-            let mut is_erase_line = true;
-            for x in EDGE_WIDTH..TETRIS_WIDTH - EDGE_WIDTH {
-                if self.tetris_board[y * TETRIS_WIDTH + x] == NONE {
-                    is_erase_line = false;
-                    break;
-                }
+        let _width = self.tetris_width();
+        let mut erase_lines: u32 = 0;
+        for y in self.full_rows() {
+            erase_lines += 1;
+            // 上の段を1つずつ下にずらし、一番上は空にする
+            self.tetris_board.copy_within(0..y * _width, _width);
+            for x in EDGE_WIDTH..EDGE_WIDTH + self.width {
+                self.tetris_board[x] = NONE;
             }
-            if is_erase_line {
-                erase_lines += 1;
-                for y2 in (0..y).rev() {
-                    for x2 in 0..TETRIS_WIDTH {
-                        self.tetris_board[(y2 + 1) * TETRIS_WIDTH + x2] =
-                            self.tetris_board[(y2) * TETRIS_WIDTH + x2];
-                    }
+        }
+        self.last_perfect_clear = erase_lines > 0 && self.is_perfect_clear();
+        self.score_lines(erase_lines, self.last_tspin, self.last_perfect_clear);
+        self.add_lines(erase_lines);
+    }
+
+    fn full_rows(&self) -> Vec<usize> {
+        //! 横一列がそろっている段を上から順に返す
+        let _width = self.tetris_width();
+        (0..self.field_height())
+            .filter(|&y| {
+                (EDGE_WIDTH..EDGE_WIDTH + self.width)
+                    .all(|x| self.tetris_board[y * _width + x] != NONE)
+            })
+            .collect()
+    }
+
+    pub fn add_garbage(&mut self, rows: usize, hole: usize) -> Result<(), EngineError> {
+        //! 下から rows 段のせり上がりを入れる 穴は全部左から hole 列目(0始まり)にそろえる
+        self.check_alive()?;
+        if hole >= self.width {
+            return Err(EngineError::InvalidGarbage);
+        }
+        self.garbage_hole = Some(hole);
+        self.raise_stack(&vec![hole; rows])
+    }
+
+    pub fn add_random_garbage(&mut self, rows: usize, messiness: f64) -> Result<(), EngineError> {
+        //! 穴の位置をランダムに決めてせり上がりを入れる
+        //! 1段ごとに messiness (0.0〜1.0) の確率で穴を別の列に変える 0.0 なら前回の穴の列にそろえる
+        self.check_alive()?;
+        if messiness.is_nan() {
+            return Err(EngineError::InvalidGarbage);
+        }
+        let _messiness = messiness.clamp(0.0, 1.0);
+        let mut _holes = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let _hole = match self.garbage_hole {
+                Some(h) if self.garbage_rng.gen_bool(_messiness) => {
+                    (h + self.garbage_rng.gen_range(1..self.width)) % self.width
                 }
+                Some(h) => h,
+                None => self.garbage_rng.gen_range(0..self.width),
+            };
+            self.garbage_hole = Some(_hole);
+            _holes.push(_hole);
+        }
+        self.raise_stack(&_holes)
+    }
+
+    fn raise_stack(&mut self, holes: &[usize]) -> Result<(), EngineError> {
+        //! 積んだブロックと操作中のブロックを holes の数だけ押し上げ、下に穴あきの段を足す
+        //! 盤面の上端より上に押し出されたらトップアウト
+        let _width = self.tetris_width();
+        let _height = self.field_height();
+        let _rows = holes.len();
+        let _top_out = (0.._rows.min(_height)).any(|y| {
+            (EDGE_WIDTH..EDGE_WIDTH + self.width).any(|x| self.tetris_board[y * _width + x] != NONE)
+        });
+        for y in 0.._height {
+            for x in EDGE_WIDTH..EDGE_WIDTH + self.width {
+                self.tetris_board[y * _width + x] = if y + _rows < _height {
+                    self.tetris_board[(y + _rows) * _width + x]
+                } else if x - EDGE_WIDTH == holes[y + _rows - _height] {
+                    NONE
+                } else {
+                    GARBAGE
+                };
             }
         }
-        self.add_score(erase_lines * erase_lines * LINE_BASE_SCORE);
+        for _row in self.clearing_rows.iter_mut() {
+            *_row = _row.saturating_sub(_rows);
+        }
+        self.emit(GameEvent::GarbageAdded { rows: _rows });
+        if _top_out {
+            return self.end_game(GameOver::TopOut);
+        }
+        if self.phase != Phase::Falling {
+            return Ok(()); // 押し上げる操作中のブロックがない
+        }
+        self.block_position.y = self.block_position.y.saturating_sub(_rows);
+        self.lowest_y = self.lowest_y.saturating_sub(_rows);
+        if self.is_collision(&self.block_position) {
+            return self.end_game(GameOver::TopOut);
+        }
+        self.ghost_pos();
+        Ok(())
+    }
+
+    pub fn is_perfect_clear(&self) -> bool {
+        //! 壁の内側にブロックが1つも残っていないか
+        let _width = self.tetris_width();
+        (0..self.field_height()).all(|y| {
+            (EDGE_WIDTH..EDGE_WIDTH + self.width)
+                .all(|x| self.tetris_board[y * _width + x] == NONE)
+        })
     }
 
-    pub fn next_block(&mut self) -> Result<(), ()> {
+    fn score_lines(&mut self, lines: u32, tspin: TSpin, perfect_clear: bool) {
+        //! ライン消去の得点 Back-to-Back とコンボもここで更新する
+        let _rule = self.config.scoring;
+        if lines == 0 {
+            // ラインを消さないTスピンにも得点は入るが Back-to-Back は変えない
+            self.combo = -1;
+            self.add_score(_rule.line_clear(0, tspin, self.level, false));
+            return;
+        }
+        self.combo += 1;
+        let _difficult = lines >= 4 || tspin != TSpin::None;
+        let _b2b = _difficult && self.back_to_back;
+        self.add_score(_rule.line_clear(lines, tspin, self.level, _b2b));
+        if perfect_clear {
+            self.add_score(_rule.perfect_clear(lines, self.level, _b2b));
+        }
+        self.add_score(_rule.combo(self.combo as u32, self.level));
+        self.back_to_back = _difficult;
+        self.emit(GameEvent::LinesCleared {
+            lines,
+            tspin,
+            perfect_clear,
+            back_to_back: _b2b,
+            combo: self.combo,
+        });
+    }
+
+    fn add_lines(&mut self, lines: u32) {
+        //! 消したライン数を加算し、規定数に達したらレベルと落下速度を上げる
+        self.lines += lines;
+        let per = self.config.lines_per_level.max(1);
+        let _level = self.config.start_level.max(1) + self.lines / per;
+        if _level > self.level {
+            self.level = _level;
+            self.gravity = gravity_for(self.config.gravity_table, _level);
+            self.emit(GameEvent::LevelUp { level: _level });
+        }
+    }
+
+    fn next_block(&mut self) -> Result<(), EngineError> {
         //! 次に表示させるブロックの処理を行う関数
         self.block_placed += 1;
+        self.hold_used = false;
         self.block_rotate = 0;
-        self.block_position = Position::init(); // 次のブロックの処理
-        self.block_now_shape = self.block_next_three[0]; // ブロックが固定されたら変数を変えて出てくるブロックを変える
+        self.block_position = self.spawn_position(); // 次のブロックの処理
+        self.block_now_shape = self.advance_queue(); // ブロックが固定されたら変数を変えて出てくるブロックを変える
 
-        let mut _new_three_array: [TetrisBlocks; 3] = [TetrisBlocks::I; 3];
-        _new_three_array[0] = self.block_next_three[1];
-        _new_three_array[1] = self.block_next_three[2];
-        _new_three_array[2] = rand::random::<TetrisBlocks>();
-        self.block_next_three = _new_three_array; // 1つずらす
+        if self.config.ihs && self.input.hold {
+            return self.swap_hold(true); // 先行ホールド
+        }
+        self.spawn_piece(true)
+    }
+
+    fn advance_queue(&mut self) -> TetrisBlocks {
+        //! ネクストの先頭を取り出し、末尾をランダマイザで補充する
+        //! ネクストを表示しない設定(0個)ならランダマイザから直接取り出す
+        self.block_next.push_back(self.randomizer.next(&mut self.rng));
+        self.block_next.pop_front().unwrap_or(TetrisBlocks::I)
+    }
 
+    fn spawn_piece(&mut self, initial: bool) -> Result<(), EngineError> {
+        //! 出現位置に置いたブロックの後処理 重なっていたらブロックアウト
+        //! ネクストから出てきたとき(initial)に回転キーが押されたままなら回した向きで出す
+        //! 回すと重なるなら回す前の向きのまま
+        let _irs = self.input.initial_rotation().filter(|_| initial && self.config.irs);
+        if let Some(_rotate) = _irs {
+            let _before = self.block_rotate;
+            self.block_rotate = (_before + _rotate) % 4;
+            if self.is_collision(&self.block_position) {
+                self.block_rotate = _before;
+            }
+        }
         self.ghost_pos();
+        self.reset_piece_state();
 
         if self.is_collision(&self.block_position) {
-            Err(())
+            self.end_game(GameOver::BlockOut)
         } else {
+            self.emit_spawned();
             Ok(())
         }
     }
 
-    pub fn hold_block(&mut self) -> Result<(), ()> {
+    pub fn hold_block(&mut self) -> Result<(), EngineError> {
         //! ホールドに関する関数
+        //! ホールドは1つのブロックにつき1回まで 固定されるまではエラーを返す
+        self.check_active()?;
+        self.swap_hold(false)
+    }
+
+    fn swap_hold(&mut self, initial: bool) -> Result<(), EngineError> {
+        //! 今のブロックとホールドを入れ替える initial なら出現時の先行ホールドなので先行回転も行う
+        if self.hold_used {
+            return Err(EngineError::HoldUsed);
+        }
+        self.hold_used = true;
+        self.emit(GameEvent::Hold {
+            block: self.block_now_shape,
+        });
 
-        if self.block_hold as usize != TetrisBlocks::NONE as usize {
+        if self.block_hold != TetrisBlocks::NONE {
             //HOLDをすでにしてるなら
             let _tmpblock = self.block_now_shape;
             let _tmprotate: u8 = self.block_rotate;
 
             self.block_now_shape = self.block_hold;
-            self.block_rotate = self.hold_rotate;
+            self.block_rotate = if self.config.hold_keeps_rotation {
+                self.hold_rotate
+            } else {
+                0 // 出現時の向きに戻す
+            };
 
             self.block_hold = _tmpblock;
-            self.block_rotate = _tmprotate;
+            self.hold_rotate = _tmprotate;
 
-            self.block_position = Position::init();
+            self.block_position = self.spawn_position();
         } else {
             // 始めてHOLDなら
             self.block_hold = self.block_now_shape;
             self.hold_rotate = self.block_rotate;
 
-            self.block_position = Position::init(); // 次のブロックの処理
-            self.block_now_shape = self.block_next_three[0]; // ブロックが固定されたら変数を変えて出てくるブロックを変える
-
-            let mut _new_three_array: [TetrisBlocks; 3] = [TetrisBlocks::I; 3];
-            _new_three_array[0] = self.block_next_three[1];
-            _new_three_array[1] = self.block_next_three[2];
-            _new_three_array[2] = rand::random::<TetrisBlocks>();
-            self.block_next_three = _new_three_array; // 1つずらす
+            self.block_rotate = 0;
+            self.block_position = self.spawn_position(); // 次のブロックの処理
+            self.block_now_shape = self.advance_queue(); // ブロックが固定されたら変数を変えて出てくるブロックを変える
         }
 
-        self.ghost_pos(); //ゴースト表示
-        if self.is_collision(&self.block_position) {
-            Err(())
-        } else {
-            Ok(())
-        }
-    }
-
-    pub fn gameover(&self) {
-        TetrisBoard::debug_draw(self);
-        println!("GAMEOVER");
-        println!("press `q` key to exit");
+        self.spawn_piece(initial)
     }
 
-    pub fn block_fixing(&mut self) {
+    fn block_fixing(&mut self) {
         //! ブロックの固定を行う関数
-        let _b: BlockShape = BLOCKS[self.block_now_shape as usize].rotate(self.block_rotate);
+        self.last_tspin = self.detect_tspin();
+        self.emit(GameEvent::Locked {
+            block: self.block_now_shape,
+            position: self.block_position,
+            rotate: self.block_rotate,
+            tspin: self.last_tspin,
+        });
+        let _b: BlockShape = self.block_now_shape.shape(self.block_rotate);
         let gy = self.block_position.y;
         let gx = self.block_position.x;
+        let _width = self.tetris_width();
         for y in 0..4 {
             for x in 0..4 {
                 if _b[y][x] != NONE {
-                    self.tetris_board[(y + gy) * TETRIS_WIDTH + x + gx] = _b[y][x];
+                    self.tetris_board[(y + gy) * _width + x + gx] = _b[y][x];
                     // block を固定
                 }
             }
         }
     }
 
-    pub fn super_rotation(&self) -> Result<Position, ()> {
-        //! スーパーローテートの関数
+    pub fn detect_tspin(&self) -> TSpin {
+        //! Tスピンの判定 回転で入れたTの3x3の四隅のうち3つ以上が埋まっていればTスピン
+        //! 凸側の2つが埋まっているか、5番目の壁蹴りで入れたなら Full、そうでなければ Mini
+        if self.block_now_shape != TetrisBlocks::T
+            || !matches!(self.last_action, LastAction::Rotate | LastAction::Rotate180)
+        {
+            return TSpin::None;
+        }
         let pos = self.block_position;
-        let diff_pos = [
-            // 上
-            Position {
-                x: pos.x,
-                y: pos.y.checked_sub(1).unwrap_or(pos.y),
-            },
-            // 右
-            Position {
-                x: pos.x + 1,
-                y: pos.y,
-            },
-            // 下
-            Position {
-                x: pos.x,
-                y: pos.y + 1,
-            },
-            // 左
-            Position {
-                x: pos.x.checked_sub(1).unwrap_or(pos.x),
-                y: pos.y,
-            },
-            //さらに上
-            Position {
-                x: pos.x,
-                y: pos.y.checked_sub(2).unwrap_or(pos.y),
-            },
-            // さらに右
-            Position {
-                x: pos.x + 2,
-                y: pos.y,
-            },
-            // さらに下
-            Position {
-                x: pos.x,
-                y: pos.y + 2,
-            },
-            // さらに左
-            Position {
-                x: pos.x.checked_sub(2).unwrap_or(pos.x),
-                y: pos.y,
-            },
+        let _width = self.tetris_width();
+        // 左上から右回りの順
+        let _filled = [(0, 0), (2, 0), (2, 2), (0, 2)].map(|(x, y)| {
+            let (_x, _y) = (pos.x + x, pos.y + y);
+            _x >= _width
+                || _y >= self.tetris_height()
+                || self.tetris_board[_y * _width + _x] != NONE
+        });
+        if _filled.iter().filter(|&&f| f).count() < 3 {
+            return TSpin::None;
+        }
+        let _front = self.block_rotate as usize;
+        if (_filled[_front] && _filled[(_front + 1) % 4])
+            || (self.last_action == LastAction::Rotate && self.last_kick == 4)
+        {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
+    }
+
+    fn super_rotation(&self, from: u8) -> Result<(Position, usize), EngineError> {
+        //! スーパーローテートの関数 SRSのテーブルを順に試して最初に置ける位置とテーブルの番号を返す
+        let pos = self.block_position;
+        for (kick, &(dx, dy)) in kick_table(self.block_now_shape, from, self.block_rotate)
+            .iter()
+            .enumerate()
+        {
+            // テーブルは上が正なので y は反転させる
+            let (Some(x), Some(y)) = (pos.x.checked_add_signed(dx), pos.y.checked_add_signed(-dy))
+            else {
+                continue;
+            };
+            let new_pos = Position { x, y };
+            if !self.is_collision(&new_pos) {
+                return Ok((new_pos, kick));
+            }
+        }
+        Err(EngineError::NoKick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(seed: u64) -> TetrisBoard {
+        //! 同じ操作の並びを決まった順に入れる
+        let mut board = TetrisBoard::with_seed(seed);
+        let script = [
+            Action::MoveLeft,
+            Action::RotateCw,
+            Action::HardDrop,
+            Action::Hold,
+            Action::MoveRight,
+            Action::MoveRight,
+            Action::HardDrop,
+            Action::Rotate180,
+            Action::SoftDrop,
+            Action::HardDrop,
         ];
-        for _pos in diff_pos {
-            if !Self::is_collision(&self, &_pos) {
-                return Ok(_pos);
+        for _round in 0..4 {
+            for action in script {
+                board.apply(action);
+                board.apply(Action::Tick);
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_same_board() {
+        let a = play(42);
+        let b = play(42);
+        assert_eq!(a.tetris_board, b.tetris_board);
+        assert_eq!(a.score, b.score);
+        assert_eq!(a.block_now_shape, b.block_now_shape);
+        assert_eq!(a.block_position, b.block_position);
+        assert_eq!(a.block_next, b.block_next);
+        assert_eq!(a.block_hold, b.block_hold);
+    }
+
+    fn t_with_corners(corners: &[usize], last_action: LastAction, last_kick: usize) -> TSpin {
+        //! 向き0のTを盤面の中ほどに置き、3x3の四隅(左上から右回り)のうち corners を埋めて判定する
+        let mut board = TetrisBoard::with_seed(0);
+        let pos = Position {
+            x: EDGE_WIDTH + 3,
+            y: board.buffer + 10,
+        };
+        let _width = board.tetris_width();
+        for &corner in corners {
+            let (x, y) = [(0, 0), (2, 0), (2, 2), (0, 2)][corner];
+            board.tetris_board[(pos.y + y) * _width + pos.x + x] = W;
+        }
+        board.block_now_shape = TetrisBlocks::T;
+        board.block_rotate = 0;
+        board.block_position = pos;
+        board.last_action = last_action;
+        board.last_kick = last_kick;
+        board.detect_tspin()
+    }
+
+    #[test]
+    fn tspin_needs_three_corners_after_rotation() {
+        assert_eq!(t_with_corners(&[0, 1, 3], LastAction::Rotate, 0), TSpin::Full);
+        assert_eq!(t_with_corners(&[0, 1], LastAction::Rotate, 0), TSpin::None);
+        assert_eq!(t_with_corners(&[0, 1, 3], LastAction::Move, 0), TSpin::None);
+    }
+
+    #[test]
+    fn tspin_with_one_front_corner_is_mini_unless_fifth_kick() {
+        assert_eq!(t_with_corners(&[0, 2, 3], LastAction::Rotate, 0), TSpin::Mini);
+        assert_eq!(t_with_corners(&[0, 2, 3], LastAction::Rotate, 4), TSpin::Full);
+        assert_eq!(t_with_corners(&[0, 2, 3], LastAction::Rotate180, 4), TSpin::Mini);
+    }
+
+    fn grounded(config: GameConfig) -> TetrisBoard {
+        //! 一番下まで落として接地させた盤面
+        let mut board = TetrisBoard::with_config(GameConfig {
+            seed: Some(3),
+            ..config
+        });
+        while board.apply(Action::SoftDrop).is_ok() {}
+        board
+    }
+
+    fn ticks_until_lock(board: &mut TetrisBoard) -> u64 {
+        let mut frames = 1;
+        while !board.apply(Action::Tick).locked {
+            frames += 1;
+        }
+        frames
+    }
+
+    #[test]
+    fn piece_locks_after_lock_delay_grounded_frames() {
+        let mut board = grounded(GameConfig::default());
+        assert_eq!(ticks_until_lock(&mut board), board.config.lock_delay);
+    }
+
+    #[test]
+    fn no_lock_resets_still_waits_for_lock_delay() {
+        let mut board = grounded(GameConfig {
+            lock_resets: 0,
+            ..GameConfig::default()
+        });
+        for _ in 0..5 {
+            board.apply(Action::Tick);
+        }
+        assert!(board.apply(Action::MoveLeft).is_ok()); // リセットできないので猶予はそのまま
+        assert_eq!(ticks_until_lock(&mut board), board.config.lock_delay - 5);
+    }
+
+    #[test]
+    fn last_lock_reset_gives_a_full_lock_delay() {
+        let mut board = grounded(GameConfig {
+            lock_resets: 2,
+            ..GameConfig::default()
+        });
+        for _move in [Action::MoveLeft, Action::MoveRight] {
+            for _ in 0..5 {
+                board.apply(Action::Tick);
             }
+            assert!(board.apply(_move).is_ok());
+        }
+        assert_eq!(board.lock_resets, 2);
+        for _ in 0..5 {
+            board.apply(Action::Tick);
         }
-        Err(())
+        assert!(board.apply(Action::MoveLeft).is_ok()); // 回数を使い切ったのでリセットされない
+        assert_eq!(ticks_until_lock(&mut board), board.config.lock_delay - 5);
+    }
+
+    fn wide_board(config: GameConfig) -> TetrisBoard {
+        //! 横移動を確かめるための広い盤面 Tを出現位置に置く
+        let mut board = TetrisBoard::with_config(GameConfig {
+            seed: Some(3),
+            width: 20,
+            ..config
+        });
+        board.block_now_shape = TetrisBlocks::T;
+        board
+    }
+
+    fn trace_x(board: &mut TetrisBoard, frames: usize) -> Vec<usize> {
+        //! frames フレーム進めて、それぞれのフレームの後の x を返す
+        (0..frames)
+            .map(|_| {
+                board.apply(Action::Tick);
+                board.block_position.x
+            })
+            .collect()
+    }
+
+    #[test]
+    fn das_then_arr_repeats() {
+        let mut board = wide_board(GameConfig::default());
+        let x = board.block_position.x;
+        board.apply(Action::Press(Input::Left));
+        assert_eq!(board.block_position.x, x - 1); // 押した瞬間に1マス
+        let mut expected = vec![x - 1; 9];
+        expected.extend([x - 2, x - 2, x - 3, x - 3, x - 4]); // 10フレーム目から2フレームごと
+        assert_eq!(trace_x(&mut board, 14), expected);
+    }
+
+    #[test]
+    fn zero_arr_shifts_straight_to_the_wall() {
+        let mut board = wide_board(GameConfig {
+            arr: 0,
+            ..GameConfig::default()
+        });
+        let x = board.block_position.x;
+        board.apply(Action::Press(Input::Left));
+        let mut expected = vec![x - 1; 9];
+        expected.push(EDGE_WIDTH);
+        assert_eq!(trace_x(&mut board, 10), expected);
     }
 
+    #[test]
+    fn last_pressed_direction_wins_and_release_falls_back() {
+        let mut board = wide_board(GameConfig::default());
+        let x = board.block_position.x;
+        board.apply(Action::Press(Input::Left));
+        trace_x(&mut board, 3);
+        board.apply(Action::Press(Input::Right));
+        assert_eq!(board.block_position.x, x);
+        let mut expected = vec![x; 9];
+        expected.push(x + 1); // 右の DAS は押し直した時点から溜まる
+        assert_eq!(trace_x(&mut board, 10), expected);
+
+        board.apply(Action::Release(Input::Right));
+        let mut expected = vec![x + 1; 9];
+        expected.push(x); // 押されたままの左に戻り、DAS を溜め直す
+        assert_eq!(trace_x(&mut board, 10), expected);
+    }
+
+    #[test]
+    fn das_cut_pauses_repeat_after_rotation() {
+        let mut board = wide_board(GameConfig {
+            das_cut: 5,
+            ..GameConfig::default()
+        });
+        let x = board.block_position.x;
+        board.apply(Action::Press(Input::Left));
+        assert_eq!(trace_x(&mut board, 10)[9], x - 2);
+        assert!(board.apply(Action::RotateCw).is_ok());
+        let mut expected = vec![x - 2; 6];
+        expected.push(x - 3); // 5フレーム止まってから ARR の間隔で再開する
+        assert_eq!(trace_x(&mut board, 7), expected);
+    }
+
+    #[test]
+    fn hold_once_per_piece_until_it_locks() {
+        let mut board = TetrisBoard::with_seed(3);
+        let first = board.block_now_shape;
+        assert!(board.apply(Action::Hold).is_ok());
+        assert_eq!(board.block_hold, first);
+        assert_eq!(board.apply(Action::Hold).error, Some(EngineError::HoldUsed));
+        assert_eq!(board.block_hold, first);
+        board.apply(Action::HardDrop);
+        assert!(!board.hold_used);
+        assert!(board.apply(Action::Hold).is_ok());
+        assert_eq!(board.block_now_shape, first);
+    }
+
+    #[test]
+    fn held_piece_returns_in_spawn_orientation_unless_configured() {
+        let returned_rotation = |hold_keeps_rotation| {
+            let mut board = TetrisBoard::with_config(GameConfig {
+                seed: Some(3),
+                hold_keeps_rotation,
+                ..GameConfig::default()
+            });
+            assert!(board.apply(Action::RotateCw).is_ok());
+            board.apply(Action::Hold);
+            board.apply(Action::HardDrop);
+            board.apply(Action::Hold);
+            assert_eq!(board.block_position, board.spawn_position());
+            board.block_rotate
+        };
+        assert_eq!(returned_rotation(false), 0);
+        assert_eq!(returned_rotation(true), 1);
+    }
+
+    #[test]
+    fn irs_applies_to_queue_spawn_but_not_to_mid_fall_hold() {
+        let mut board = TetrisBoard::with_seed(3);
+        board.apply(Action::Press(Input::RotateCw));
+        assert!(board.apply(Action::Hold).is_ok());
+        assert_eq!(board.block_rotate, 0); // 落下中のホールドは出現時の向き
+        board.apply(Action::HardDrop);
+        assert_eq!(board.block_rotate, 1); // ネクストから出てきたときは先行回転
+    }
+
+    fn drop_i_into_bottom_row(config: GameConfig) -> TetrisBoard {
+        //! 一番下の段をIが入る4列だけ空けて埋め、Iをハードドロップして1ライン消す
+        let mut board = TetrisBoard::with_config(GameConfig {
+            seed: Some(3),
+            ..config
+        });
+        board.block_now_shape = TetrisBlocks::I;
+        let _width = board.tetris_width();
+        let _bottom = board.field_height() - 1;
+        for x in (EDGE_WIDTH..EDGE_WIDTH + 3).chain(EDGE_WIDTH + 7..EDGE_WIDTH + board.width) {
+            board.tetris_board[_bottom * _width + x] = GARBAGE;
+        }
+        assert!(board.apply(Action::HardDrop).locked);
+        board
+    }
+
+    #[test]
+    fn line_clear_delay_erases_rows_on_its_last_frame() {
+        let mut board = drop_i_into_bottom_row(GameConfig {
+            line_clear_delay: 5,
+            ..GameConfig::default()
+        });
+        assert_eq!(board.phase, Phase::LineClear(5));
+        assert_eq!(board.clearing_rows, vec![board.field_height() - 1]);
+        for _ in 1..5 {
+            assert_eq!(board.apply(Action::Tick).lines, 0);
+        }
+        assert_eq!(board.apply(Action::Tick).lines, 1);
+        assert!(board.clearing_rows.is_empty());
+        assert!(board.is_perfect_clear());
+    }
+
+    #[test]
+    fn are_spawns_the_next_piece_on_its_last_frame() {
+        let mut board = TetrisBoard::with_config(GameConfig {
+            seed: Some(3),
+            are: 5,
+            ..GameConfig::default()
+        });
+        let next = board.block_next[0];
+        board.apply(Action::HardDrop);
+        assert_eq!(board.phase, Phase::Entry(5));
+        for _ in 1..5 {
+            board.apply(Action::Tick);
+            assert_eq!(board.apply(Action::MoveLeft).error, Some(EngineError::NoPiece));
+            assert_eq!(board.apply(Action::Hold).error, Some(EngineError::NoPiece));
+        }
+        board.apply(Action::Tick);
+        assert_eq!(board.phase, Phase::Falling);
+        assert_eq!(board.block_now_shape, next);
+        assert!(board.apply(Action::MoveLeft).is_ok());
+    }
+
+    #[test]
+    fn das_charges_during_line_clear_and_are() {
+        let mut board = drop_i_into_bottom_row(GameConfig {
+            line_clear_delay: 6,
+            are: 6,
+            ..GameConfig::default()
+        });
+        board.apply(Action::Press(Input::Left));
+        trace_x(&mut board, 12); // 最後のフレームで次のブロックが出る
+        assert_eq!(board.phase, Phase::Falling);
+        let x = board.block_position.x;
+        assert_eq!(x, board.spawn_position().x);
+        // 待ちの間に DAS が溜まっているので、出てすぐ ARR の間隔で動き出す
+        assert_eq!(trace_x(&mut board, 2), vec![x, x - 1]);
+    }
+
+    #[test]
+    fn garbage_during_line_clear_shifts_the_clearing_rows() {
+        let mut board = drop_i_into_bottom_row(GameConfig {
+            line_clear_delay: 5,
+            ..GameConfig::default()
+        });
+        let _bottom = board.field_height() - 1;
+        assert!(board.add_garbage(2, 0).is_ok());
+        assert_eq!(board.clearing_rows, vec![_bottom - 2]);
+        for _ in 1..5 {
+            board.apply(Action::Tick);
+        }
+        assert_eq!(board.apply(Action::Tick).lines, 1);
+        // そろった段だけが消え、せり上がりの2段は残る
+        let _width = board.tetris_width();
+        for y in [_bottom - 1, _bottom] {
+            assert_eq!(board.tetris_board[y * _width + EDGE_WIDTH], NONE);
+            assert_eq!(board.tetris_board[y * _width + EDGE_WIDTH + 1], GARBAGE);
+        }
+        assert!((EDGE_WIDTH..EDGE_WIDTH + board.width)
+            .all(|x| board.tetris_board[(_bottom - 2) * _width + x] == NONE));
+    }
+
+    fn fill_row(board: &mut TetrisBoard, y: usize) {
+        //! y 段目を左端の1列だけ空けて埋める
+        let _width = board.tetris_width();
+        for x in EDGE_WIDTH + 1..EDGE_WIDTH + board.width {
+            board.tetris_board[y * _width + x] = GARBAGE;
+        }
+    }
+
+    #[test]
+    fn blocked_spawn_is_block_out() {
+        let mut board = TetrisBoard::with_seed(3);
+        while board.apply(Action::SoftDrop).is_ok() {}
+        let _spawn = board.spawn_position().y;
+        fill_row(&mut board, _spawn);
+        fill_row(&mut board, _spawn + 1);
+        assert_eq!(board.apply(Action::HardDrop).game_over, Some(GameOver::BlockOut));
+    }
+
+    #[test]
+    fn piece_locked_above_the_visible_field_is_lock_out() {
+        let mut board = TetrisBoard::with_seed(3);
+        board.block_now_shape = TetrisBlocks::T;
+        let _buffer = board.buffer;
+        fill_row(&mut board, _buffer);
+        assert_eq!(board.apply(Action::HardDrop).game_over, Some(GameOver::LockOut));
+    }
+
+    #[test]
+    fn garbage_pushing_the_stack_off_the_top_is_top_out() {
+        let mut board = TetrisBoard::with_seed(3);
+        board.tetris_board[EDGE_WIDTH] = GARBAGE; // 一番上の段の左端
+        assert_eq!(board.add_garbage(1, 0), Err(EngineError::GameOver(GameOver::TopOut)));
+        assert_eq!(board.game_over, Some(GameOver::TopOut));
+    }
+
+    #[test]
+    fn garbage_pushing_the_stack_into_the_piece_is_top_out() {
+        let mut board = TetrisBoard::with_seed(3);
+        board.block_now_shape = TetrisBlocks::T;
+        board.block_position.y = 0; // これ以上押し上げられない位置
+        fill_row(&mut board, 3);
+        assert_eq!(board.add_garbage(2, 0), Err(EngineError::GameOver(GameOver::TopOut)));
+        assert_eq!(board.game_over, Some(GameOver::TopOut));
+    }
+
+    #[test]
+    fn garbage_does_not_change_piece_sequence() {
+        let sequence = |garbage: bool| {
+            let mut board = TetrisBoard::with_seed(42);
+            (0..8)
+                .map(|i| {
+                    if garbage && i < 4 {
+                        board.add_random_garbage(1, 0.5).unwrap();
+                    }
+                    let block = board.block_now_shape;
+                    board.apply(Action::HardDrop);
+                    block
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(false), sequence(true));
+    }
+
+    #[test]
+    fn invalid_garbage_is_rejected() {
+        let mut board = TetrisBoard::with_seed(0);
+        assert_eq!(board.add_garbage(1, board.width), Err(EngineError::InvalidGarbage));
+        assert_eq!(board.add_random_garbage(1, f64::NAN), Err(EngineError::InvalidGarbage));
+        assert!(board.add_garbage(1, board.width - 1).is_ok());
+    }
+
+    #[test]
+    fn piece_sequence_depends_only_on_seed() {
+        let sequence = |seed| {
+            let mut board = TetrisBoard::with_seed(seed);
+            (0..14)
+                .map(|_| {
+                    let block = board.block_now_shape;
+                    board.apply(Action::HardDrop);
+                    block
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(1), sequence(1));
+        assert_ne!(sequence(1), sequence(2));
+    }
 }
@@ -0,0 +1,73 @@
+// キーの押下状態と横移動のオートリピート(DAS/ARR)
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    Left,
+    Right,
+    SoftDrop,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    Hold,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InputState {
+    pub left: bool,
+    pub right: bool,
+    pub soft_drop: bool,
+    pub rotate_cw: bool,
+    pub rotate_ccw: bool,
+    pub rotate_180: bool,
+    pub hold: bool,
+    pub shift: Option<Input>, // 今リピートしている方向 後から押した方が優先
+    pub das_timer: u64,       // shift の方向を押し続けているフレーム数
+    pub arr_timer: u64,       // DAS が溜まってから次のリピートまでの経過フレーム数
+    pub das_cut_timer: u64,   // 回転後にリピートを止めておく残りフレーム数
+}
+
+impl InputState {
+    pub fn is_held(&self, input: Input) -> bool {
+        match input {
+            Input::Left => self.left,
+            Input::Right => self.right,
+            Input::SoftDrop => self.soft_drop,
+            Input::RotateCw => self.rotate_cw,
+            Input::RotateCcw => self.rotate_ccw,
+            Input::Rotate180 => self.rotate_180,
+            Input::Hold => self.hold,
+        }
+    }
+
+    pub fn set_held(&mut self, input: Input, held: bool) {
+        match input {
+            Input::Left => self.left = held,
+            Input::Right => self.right = held,
+            Input::SoftDrop => self.soft_drop = held,
+            Input::RotateCw => self.rotate_cw = held,
+            Input::RotateCcw => self.rotate_ccw = held,
+            Input::Rotate180 => self.rotate_180 = held,
+            Input::Hold => self.hold = held,
+        }
+    }
+
+    pub fn initial_rotation(&self) -> Option<u8> {
+        //! 押されたままの回転キーから先行回転(IRS)の向きを決める 右回りを優先する
+        if self.rotate_cw {
+            Some(1)
+        } else if self.rotate_ccw {
+            Some(3)
+        } else if self.rotate_180 {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    pub fn start_shift(&mut self, input: Option<Input>) {
+        //! リピートする方向を変えて DAS を溜め直す
+        self.shift = input;
+        self.das_timer = 0;
+        self.arr_timer = 0;
+    }
+}
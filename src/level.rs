@@ -0,0 +1,36 @@
+// レベルと落下速度
+// 落下速度は 1フレーム(1/60秒)あたりに落ちる段数を GRAVITY_UNIT 倍した整数で持つ (GRAVITY_UNIT = 1G)
+
+pub const FRAMES_PER_SECOND: u64 = 60;
+pub const GRAVITY_UNIT: u32 = 65536;
+pub const MAX_GRAVITY: u32 = 20 * GRAVITY_UNIT; // 20G
+
+// ガイドラインの (0.8 - (level - 1) * 0.007) ^ (level - 1) 秒/段 をレベル1から並べたもの
+pub const GUIDELINE_GRAVITY: [u32; 20] = [
+    1092,        // 1: 1/60G
+    1377,        // 2
+    1768,        // 3
+    2311,        // 4
+    3075,        // 5
+    4169,        // 6
+    5759,        // 7
+    8107,        // 8
+    11634,       // 9
+    17026,       // 10
+    25416,       // 11
+    38709,       // 12
+    60169,       // 13: ほぼ1G
+    95483,       // 14
+    154742,      // 15
+    256187,      // 16
+    433425,      // 17
+    749597,      // 18
+    MAX_GRAVITY, // 19
+    MAX_GRAVITY, // 20
+];
+
+pub fn gravity_for(table: &[u32], level: u32) -> u32 {
+    //! レベルに対応する落下速度を返す 表より上のレベルは最後の値のまま
+    let _index = (level.max(1) as usize - 1).min(table.len().saturating_sub(1));
+    table.get(_index).copied().unwrap_or(GRAVITY_UNIT).min(MAX_GRAVITY)
+}
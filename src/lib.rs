@@ -0,0 +1,12 @@
+pub mod action;
+pub mod block;
+pub mod config;
+pub mod error;
+pub mod event;
+pub mod game;
+pub mod input;
+pub mod level;
+pub mod randomizer;
+pub mod render;
+pub mod scoring;
+pub mod srs;
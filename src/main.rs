@@ -1,9 +1,7 @@
 use play::autoplay;
 
-mod block;
-mod game;
 mod play;
 
 fn main(){
     autoplay();
-}
\ No newline at end of file
+}
@@ -1,9 +1,10 @@
 use getch_rs::{Getch, Key};
 use rand::Rng;
-use std::sync::{Arc, Mutex};
 use std::{thread, time};
 
-use crate::game::{Position, TetrisBoard};
+use tetris::action::Action;
+use tetris::game::TetrisBoard;
+use tetris::render::{debug_draw, gameover};
 
 /*
 fn main() {
@@ -101,7 +102,17 @@ pub fn autoplay() {
         let mut game: TetrisBoard = TetrisBoard::new();
         loop {
             //todo!();
+            if game.game_over.is_some() {
+                break;
+            }
             ai1(&mut game);
+            game.drain_events().for_each(drop); // イベントは使わないので捨てる
+            if game.game_over.is_some() {
+                // ブロック生成不可能になったらGame Over
+                gameover(&game);
+                break;
+            }
+            debug_draw(&game);
             thread::sleep(time::Duration::from_millis(10));
         }
     });
@@ -123,32 +134,32 @@ pub fn quit() {
     println!("\x1b[?25h");
 }
 pub fn ai1(game: &mut TetrisBoard) {
+    //! 操作を決めて適用するだけ 描画は呼び出し側で行う
     let mut rng = rand::thread_rng();
     // hold
-    if rng.gen_range(0..5) == 0 {
-        if game.hold_block().is_err() {
-            game.gameover();
-        }
+    if rng.gen_range(0..5) == 0 && game.apply(Action::Hold).game_over.is_some() {
+        return;
     }
     // ランダムに回転
-    for _ in 0..rng.gen_range(0..=3) {
-        game.rotate();
+    let _rotate = match rng.gen_range(0..=3) {
+        1 => Some(Action::RotateCw),
+        2 => Some(Action::Rotate180),
+        3 => Some(Action::RotateCcw),
+        _ => None,
+    };
+    if let Some(_rotate) = _rotate {
+        game.apply(_rotate);
     }
     // ランダムに横移動
     let diff: isize = rng.gen_range(-4..=5);
-    if diff < 0 {
-        game.move_left(diff.abs() as usize);
+    let _move = if diff < 0 {
+        Action::MoveLeft
     } else {
-        game.move_right(diff.abs() as usize);
-    }
-    // ハードドロップ
-    game.hard_drop();
-
-    game.block_fixing(); // ライン固定
-    game.erase_lines(); // ライン消去
-    if game.next_block().is_err() {
-        // ブロック生成不可能になったらGame Over
-        game.gameover();
+        Action::MoveRight
+    };
+    for _ in 0..diff.unsigned_abs() {
+        game.apply(_move);
     }
-    game.debug_draw();
+    // ハードドロップ(固定・ライン消去・次のブロックまでエンジン側で行う)
+    game.apply(Action::HardDrop);
 }
@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+
+use rand::{seq::SliceRandom, Rng, RngCore};
+
+use crate::block::TetrisBlocks;
+
+// 実際に出てくる7種類のブロック
+pub const PIECES: [TetrisBlocks; 7] = [
+    TetrisBlocks::I,
+    TetrisBlocks::O,
+    TetrisBlocks::S,
+    TetrisBlocks::Z,
+    TetrisBlocks::T,
+    TetrisBlocks::J,
+    TetrisBlocks::L,
+];
+
+pub trait Randomizer: Send {
+    // 次に出てくるブロックを1つ決める 乱数は盤面側から渡す
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrisBlocks;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RandomizerKind {
+    Random,
+    #[default]
+    Bag7,
+    Bag14,
+    Tgm,
+    Nes,
+}
+impl RandomizerKind {
+    pub fn build(self) -> Box<dyn Randomizer> {
+        //! 種類に対応するランダマイザを作る
+        match self {
+            RandomizerKind::Random => Box::new(PureRandomizer),
+            RandomizerKind::Bag7 => Box::new(BagRandomizer::new(1)),
+            RandomizerKind::Bag14 => Box::new(BagRandomizer::new(2)),
+            RandomizerKind::Tgm => Box::new(HistoryRandomizer::new(6)),
+            RandomizerKind::Nes => Box::new(NesRandomizer::new()),
+        }
+    }
+}
+
+pub struct PureRandomizer;
+impl Randomizer for PureRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrisBlocks {
+        //! 毎回7種類から一様に選ぶ
+        rng.gen::<TetrisBlocks>()
+    }
+}
+
+pub struct BagRandomizer {
+    bag: Vec<TetrisBlocks>,
+    copies: usize,
+}
+impl BagRandomizer {
+    pub fn new(copies: usize) -> Self {
+        //! copies セット分(7 * copies 個)を1つの袋にする
+        BagRandomizer {
+            bag: Vec::new(),
+            copies: copies.max(1),
+        }
+    }
+}
+impl Default for BagRandomizer {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+impl Randomizer for BagRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrisBlocks {
+        //! 袋に入れてシャッフルし、後ろから取り出す 空になったら詰め直す
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend_from_slice(&PIECES);
+            }
+            self.bag.shuffle(rng);
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+pub struct HistoryRandomizer {
+    history: VecDeque<TetrisBlocks>,
+    rolls: usize,
+    first: bool,
+}
+impl HistoryRandomizer {
+    pub fn new(rolls: usize) -> Self {
+        //! TGM方式 直近4個の履歴にあるブロックは rolls 回まで引き直す
+        HistoryRandomizer {
+            history: VecDeque::from([
+                TetrisBlocks::Z,
+                TetrisBlocks::S,
+                TetrisBlocks::S,
+                TetrisBlocks::Z,
+            ]),
+            rolls: rolls.max(1),
+            first: true,
+        }
+    }
+}
+impl Randomizer for HistoryRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrisBlocks {
+        //! 最初の1個は S, Z, O 以外から選ぶ 引き直しきったら最後の結果をそのまま使う
+        let mut _piece = *PIECES.choose(rng).unwrap();
+        if self.first {
+            self.first = false;
+            _piece = *[TetrisBlocks::I, TetrisBlocks::T, TetrisBlocks::J, TetrisBlocks::L]
+                .choose(rng)
+                .unwrap();
+        } else {
+            for _ in 1..self.rolls {
+                if !self.history.contains(&_piece) {
+                    break;
+                }
+                _piece = *PIECES.choose(rng).unwrap();
+            }
+        }
+        self.history.pop_front();
+        self.history.push_back(_piece);
+        _piece
+    }
+}
+
+#[derive(Default)]
+pub struct NesRandomizer {
+    prev: Option<TetrisBlocks>,
+}
+impl NesRandomizer {
+    pub fn new() -> Self {
+        NesRandomizer { prev: None }
+    }
+}
+impl Randomizer for NesRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrisBlocks {
+        //! NES方式 8通りで引いて、ハズレか直前と同じなら1回だけ7通りで引き直す
+        let _roll = rng.gen_range(0..8);
+        let _piece = match PIECES.get(_roll) {
+            Some(&p) if Some(p) != self.prev => p,
+            _ => PIECES[rng.gen_range(0..7)],
+        };
+        self.prev = Some(_piece);
+        _piece
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn count(pieces: &[TetrisBlocks], block: TetrisBlocks) -> usize {
+        pieces.iter().filter(|&&p| p == block).count()
+    }
+
+    #[test]
+    fn bag_cycles_contain_every_piece_once_per_copy() {
+        for copies in [1, 2] {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut bag = BagRandomizer::new(copies);
+            for _ in 0..20 {
+                let cycle: Vec<TetrisBlocks> = (0..7 * copies).map(|_| bag.next(&mut rng)).collect();
+                for block in PIECES {
+                    assert_eq!(count(&cycle, block), copies, "{:?} in {:?}", block, cycle);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tgm_first_piece_is_never_s_z_or_o() {
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let first = HistoryRandomizer::new(6).next(&mut rng);
+            assert!(
+                !matches!(first, TetrisBlocks::S | TetrisBlocks::Z | TetrisBlocks::O),
+                "seed {} gave {:?}",
+                seed,
+                first
+            );
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::block::{tetris_blocks::GHOST, tetris_blocks::NONE, BlockShape};
+use crate::game::{Phase, TetrisBoard, EDGE_WIDTH};
+use crate::scoring::TSpin;
+
+// 端末への描画 盤面(game.rs)は入出力をしないので、表示はすべてここで行う
+
+pub const COLOR_TABLE: [&str; 11] = [
+    "\x1b[48;2;000;000;000m  ", // 何もなし
+    "\x1b[48;2;127;127;127m__", // 壁
+    "\x1b[48;2;000;000;255m__", // I
+    "\x1b[48;2;000;255;000m__", // O
+    "\x1b[48;2;000;255;255m__", // S
+    "\x1b[48;2;255;000;000m__", // Z
+    "\x1b[48;2;255;000;255m__", // J
+    "\x1b[48;2;255;127;000m__", // L
+    "\x1b[48;2;255;255;000m__", // T
+    "\x1b[48;2;000;000;000m[]", // ゴースト
+    "\x1b[48;2;095;095;095m__", // せり上がり
+];
+const CLEARING: &str = "\x1b[48;2;255;255;255m  "; // ライン消去の待ち中に消える段
+
+#[allow(clippy::needless_range_loop)]
+pub fn debug_draw(board: &TetrisBoard) {
+    //! 盤面を描画する関数 Debug情報込みで余計なものも多い
+    let mut field_buffer = board.tetris_board.clone();
+    let _b: BlockShape = board.block_now_shape.shape(board.block_rotate);
+    let mut _x: usize; // 一次的変数
+    let mut _y: usize; // 一次的変数
+    let mut _board_position: usize; // 一次的変数
+    let _g = board.ghost;
+    let _active = board.phase == Phase::Falling; // 待ちの間は操作中のブロックを描かない
+    for y in 0..4 {
+        for x in 0..4 {
+            if _active && _b[y][x] != NONE {
+                _x = x + board.block_position.x;
+                _y = y + board.block_position.y;
+                _board_position = _y * board.tetris_width() + _x;
+                field_buffer[_board_position] = _b[y][x];
+                field_buffer[(_g.y + y) * board.tetris_width() + _g.x + x] = GHOST;
+            }
+        }
+    }
+    println!("\x1b[2J\x1b[H\x1b[?25l");
+    for i in board.buffer - 2..=board.field_height() {
+        //y 隠れた領域は出現位置の2段だけ表示する
+        for j in EDGE_WIDTH - 1..=EDGE_WIDTH + board.width {
+            if board.clearing_rows.contains(&i) && j >= EDGE_WIDTH && j < EDGE_WIDTH + board.width {
+                print!("{}", CLEARING);
+            } else {
+                print!("{}", COLOR_TABLE[field_buffer[i * board.tetris_width() + j]]);
+            }
+        }
+        print!("\x1b[49;0;0;0m "); // リセット
+        println!(); // 改行
+    }
+    println!("Now Block is {:?}", board.block_now_shape);
+    if board.hold_used {
+        println!("\x1b[90mHold: {:?}\x1b[39m", board.block_hold); // 使用済みは灰色
+    } else {
+        println!("Hold: {:?}", board.block_hold);
+    }
+    println!("{:?}", board);
+    println!("Now Pointing area is {:?}", board.block_position);
+    match board.last_tspin {
+        TSpin::Full => println!("T-SPIN"),
+        TSpin::Mini => println!("T-SPIN MINI"),
+        TSpin::None => (),
+    }
+    if board.last_perfect_clear {
+        println!("PERFECT CLEAR");
+    }
+    print!("\x1b[49;0;0;0m "); // リセット
+}
+
+pub fn gameover(board: &TetrisBoard) {
+    debug_draw(board);
+    match board.game_over {
+        Some(reason) => println!("GAMEOVER ({})", reason),
+        None => println!("GAMEOVER"),
+    }
+    println!("press `q` key to exit");
+}
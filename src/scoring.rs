@@ -0,0 +1,121 @@
+// 得点の計算方法
+
+pub const LINE_BASE_SCORE: i32 = 100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TSpin {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScoringRule {
+    #[default]
+    Guideline, // ガイドライン準拠 レベル倍率・Back-to-Back・コンボあり
+    Classic,   // 以前からの 消したライン数^2 * 100 (Tスピンは数えない)
+}
+
+impl ScoringRule {
+    pub fn line_clear(&self, lines: u32, tspin: TSpin, level: u32, back_to_back: bool) -> i32 {
+        //! ライン消去の得点 back_to_back は直前も難しい消し方だったか
+        match self {
+            ScoringRule::Guideline => {
+                let _base = match (tspin, lines) {
+                    (TSpin::None, 0) => 0,
+                    (TSpin::None, 1) => 100,
+                    (TSpin::None, 2) => 300,
+                    (TSpin::None, 3) => 500,
+                    (TSpin::None, _) => 800,
+                    (TSpin::Mini, 0) => 100,
+                    (TSpin::Mini, 1) => 200,
+                    (TSpin::Mini, _) => 400,
+                    (TSpin::Full, 0) => 400,
+                    (TSpin::Full, 1) => 800,
+                    (TSpin::Full, 2) => 1200,
+                    (TSpin::Full, _) => 1600,
+                } * level as i32;
+                if back_to_back {
+                    _base * 3 / 2
+                } else {
+                    _base
+                }
+            }
+            ScoringRule::Classic => (lines * lines) as i32 * LINE_BASE_SCORE,
+        }
+    }
+
+    pub fn perfect_clear(&self, lines: u32, level: u32, back_to_back: bool) -> i32 {
+        //! 盤面を全部消したときのボーナス ライン消去の得点とは別に加算する
+        match self {
+            ScoringRule::Guideline => {
+                let _base = match (lines, back_to_back) {
+                    (0, _) => 0,
+                    (1, _) => 800,
+                    (2, _) => 1200,
+                    (3, _) => 1800,
+                    (_, false) => 2000,
+                    (_, true) => 3200, // Back-to-Back のテトリス
+                };
+                _base * level as i32
+            }
+            ScoringRule::Classic => 0,
+        }
+    }
+
+    pub fn combo(&self, combo: u32, level: u32) -> i32 {
+        //! 連続でラインを消したときのボーナス combo は2回目の消去で1
+        match self {
+            ScoringRule::Guideline => 50 * combo as i32 * level as i32,
+            ScoringRule::Classic => 0,
+        }
+    }
+
+    pub fn soft_drop(&self, cells: u32) -> i32 {
+        //! ソフトドロップで落とした段数ぶんの得点
+        match self {
+            ScoringRule::Guideline => cells as i32,
+            ScoringRule::Classic => 0,
+        }
+    }
+
+    pub fn hard_drop(&self, cells: u32) -> i32 {
+        //! ハードドロップで落とした段数ぶんの得点
+        match self {
+            ScoringRule::Guideline => 2 * cells as i32,
+            ScoringRule::Classic => cells as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guideline_line_clear_values() {
+        let rule = ScoringRule::Guideline;
+        assert_eq!(rule.line_clear(1, TSpin::None, 1, false), 100);
+        assert_eq!(rule.line_clear(4, TSpin::None, 1, false), 800);
+        assert_eq!(rule.line_clear(4, TSpin::None, 1, true), 1200);
+        assert_eq!(rule.line_clear(1, TSpin::Mini, 1, false), 200);
+        assert_eq!(rule.line_clear(2, TSpin::Full, 2, false), 2400);
+        assert_eq!(rule.line_clear(0, TSpin::Full, 1, false), 400);
+    }
+
+    #[test]
+    fn guideline_perfect_clear_values() {
+        let rule = ScoringRule::Guideline;
+        assert_eq!(rule.perfect_clear(1, 3, false), 2400);
+        assert_eq!(rule.perfect_clear(4, 1, false), 2000);
+        assert_eq!(rule.perfect_clear(4, 1, true), 3200);
+    }
+
+    #[test]
+    fn classic_rule_keeps_square_formula() {
+        let rule = ScoringRule::Classic;
+        assert_eq!(rule.line_clear(2, TSpin::Full, 5, true), 400);
+        assert_eq!(rule.perfect_clear(4, 1, true), 0);
+    }
+}
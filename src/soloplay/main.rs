@@ -1,39 +1,82 @@
-mod block;
-mod game;
-
-use game::{Position, TetrisBoard, DELTA};
+use clap::Parser;
 use getch_rs::{Getch, Key};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use std::{thread, time};
+use tetris::action::Action;
+use tetris::game::TetrisBoard;
+use tetris::render::{debug_draw, gameover};
+use tetris::input::Input;
+use tetris::level::FRAMES_PER_SECOND;
+
+// 端末ではキーを離したことがわからないので、キーが KEY_RELEASE の間来なければ離したとみなす
+// 1回だけ押したときはすぐ離したことになるので、横移動は1マス、ソフトドロップは数段で止まる
+// 押し続けると OS のリピート開始の遅れ(普通 250〜600ms)の間に一度離したことになるが、
+// --repeat-delay 以内に同じキーがもう一度来たらリピートとみなして押し直し、
+// 最初に押してからの時間だけ DAS を溜めておく (DAS が OS の遅れのぶん遅れないようにする)
+// --repeat-delay が短いと OS のリピートが来る前に忘れてしまい DAS が溜め直しになり、
+// 長いと素早く2回押したときもリピートとみなされて流れやすくなる
+const KEY_RELEASE: u128 = 100; // この間次のキーが来なければ離したとみなす(ms)
+
+#[derive(Parser)]
+struct Args {
+    /// OS のキーリピートが始まるまでの時間(ms) 離してからこの間に同じキーが来たら押し続けているとみなす
+    #[arg(long, default_value_t = 500)]
+    repeat_delay: u128,
+}
+
+#[derive(Clone, Copy)]
+struct HeldKey {
+    input: Input,
+    pressed: Instant, // 最初に押した時刻
+    seen: Instant,    // 最後にキーが来た時刻
+    down: bool,       // エンジンに押したと伝えているか
+}
 
 fn main() {
+    let args = Args::parse();
     println!("\x1b[2J\x1b[H\x1b[?25l");
     let tet = Arc::new(Mutex::new(TetrisBoard::new()));
-    TetrisBoard::debug_draw(&tet.lock().unwrap()); //draw
+    let held: Arc<Mutex<Option<HeldKey>>> = Arc::new(Mutex::new(None)); // 最後に押したキー
+    debug_draw(&tet.lock().unwrap()); //draw
     {
         let tet = Arc::clone(&tet);
+        let held = Arc::clone(&held);
         let _ = thread::spawn(move || {
+            let frame = time::Duration::from_nanos(1_000_000_000 / FRAMES_PER_SECOND);
+            let start = Instant::now();
+            let mut frames: u64 = 0; // エンジンに渡したフレーム数
             loop {
-                thread::sleep(time::Duration::from_millis(DELTA)); // wait for delta
+                thread::sleep(frame); // wait for tick
                 let mut tet = tet.lock().unwrap();
-                // 自由落下とボードの更新
-                let new_pos = Position {
-                    x: tet.block_position.x,
-                    y: tet.block_position.y + 1,
-                };
-                if !tet.is_collision(&new_pos) {
-                    tet.block_position = new_pos; // free fall and position update
-                } else {
-                    //cannot move downward anymore
-                    tet.block_fixing(); // ライン固定
-                    tet.erase_lines(); // ライン消去
-                    if tet.next_block().is_err() {
-                        // ブロック生成不可能になったらGame Over
-                        tet.gameover();
-                        break;
+                let mut held = held.lock().unwrap();
+                if let Some(key) = held.as_mut() {
+                    let idle = key.seen.elapsed().as_millis();
+                    if key.down && idle > KEY_RELEASE {
+                        tet.apply(Action::Release(key.input));
+                        key.down = false; // OS のリピートが来るかもしれないのでまだ覚えておく
+                    }
+                    if idle > args.repeat_delay {
+                        *held = None;
                     }
                 }
-                tet.debug_draw();
+                // 自由落下・固定の猶予・横移動のリピートはエンジン側でフレーム単位に処理する
+                // sleep がずれても、始まってからの経過時間ぶんのフレームをまとめて進める
+                let due = start.elapsed().as_nanos() as u64 * FRAMES_PER_SECOND / 1_000_000_000;
+                let mut over = false;
+                while frames < due && !over {
+                    frames += 1;
+                    over = tet.apply(Action::Tick).game_over.is_some();
+                }
+                if over {
+                    // ブロック生成不可能になったらGame Over
+                    gameover(&tet);
+                    break;
+                }
+                if tet.drain_events().count() > 0 {
+                    // 何か起きていたら描き直す
+                    debug_draw(&tet);
+                }
             }
         });
     }
@@ -41,50 +84,66 @@ fn main() {
     // key input
     let g = Getch::new();
     loop {
-        match g.getch() {
+        let key = g.getch();
+        if let Ok(Key::Char('q')) | Ok(Key::Esc) = key {
+            break;
+        }
+        if tet.lock().unwrap().game_over.is_some() {
+            continue; // ゲームオーバー後は終了キー以外受け付けない
+        }
+        match key {
             //キー入力
-            Ok(Key::Char('q')) | Ok(Key::Esc) => {
-                break;
-            }
             Ok(Key::Left) => {
                 let mut tet = tet.lock().unwrap();
-                tet.move_left(1);
-                tet.ghost_pos(); // ゴーストの計算
-                tet.debug_draw();
+                hold_key(&mut tet, &mut held.lock().unwrap(), Input::Left);
             }
             Ok(Key::Right) => {
                 let mut tet = tet.lock().unwrap();
-                tet.move_right(1);
-                tet.ghost_pos(); // ゴーストの計算
-                tet.debug_draw();
+                hold_key(&mut tet, &mut held.lock().unwrap(), Input::Right);
             }
             Ok(Key::Down) => {
                 let mut tet = tet.lock().unwrap();
-                tet.move_down(1);
-                tet.ghost_pos(); // ゴーストの計算
-                tet.debug_draw();
+                hold_key(&mut tet, &mut held.lock().unwrap(), Input::SoftDrop);
             }
             Ok(Key::Up) => {
                 let mut tet = tet.lock().unwrap();
-                tet.hard_drop();        // すぐ次のブロックの処理になるのでゴースト計算をしなくてよい
-                tet.debug_draw();
+                if tet.apply(Action::HardDrop).game_over.is_some() {
+                    // ブロック生成不可能になったらGame Over
+                    gameover(&tet);
+                } else {
+                    debug_draw(&tet);
+                }
             }
-            Ok(Key::Char(' ')) => {
+            Ok(Key::Char(' ')) | Ok(Key::Char('z')) => {
+                // 左回転
                 let mut tet = tet.lock().unwrap();
-
-                tet.rotate();
-                tet.check_rotate();
-                tet.ghost_pos(); // ゴーストの計算
-                tet.debug_draw();
+                if tet.apply(Action::RotateCcw).is_ok() {
+                    debug_draw(&tet);
+                }
+            }
+            Ok(Key::Char('x')) => {
+                // 右回転
+                let mut tet = tet.lock().unwrap();
+                if tet.apply(Action::RotateCw).is_ok() {
+                    debug_draw(&tet);
+                }
+            }
+            Ok(Key::Char('a')) => {
+                // 180度回転
+                let mut tet = tet.lock().unwrap();
+                if tet.apply(Action::Rotate180).is_ok() {
+                    debug_draw(&tet);
+                }
             }
             Ok(Key::Char('h')) => {
                 // ホールド
                 let mut tet = tet.lock().unwrap();
-                if tet.hold_block().is_err() {
-                    // ブロック生成不可能になったらGame Over
-                    tet.gameover();
-                    break;
-                }
+                let step = tet.apply(Action::Hold);
+                if step.game_over.is_some() {
+                    gameover(&tet); // ブロック生成不可能になったらGame Over
+                } else if step.is_ok() {
+                    debug_draw(&tet);
+                } // エラーならこのブロックではもうホールドできない
             }
             _ => (),
         }
@@ -92,6 +151,44 @@ fn main() {
     quit();
 }
 
+fn hold_key(tet: &mut TetrisBoard, held: &mut Option<HeldKey>, input: Input) {
+    //! 横移動・ソフトドロップのキーを押したとみなす キーが続けて来ている間は押したまま
+    let now = Instant::now();
+    match held.as_mut() {
+        Some(key) if key.input == input && key.down => key.seen = now, // リピート中
+        Some(key) if key.input == input => {
+            // 一度離したとみなした後に来たのは OS のリピート 押し直して、押してからの時間だけ DAS を溜める
+            press_key(tet, input);
+            if tet.input.shift == Some(input) {
+                let elapsed = key.pressed.elapsed().as_millis() as u64;
+                tet.input.das_timer = elapsed * FRAMES_PER_SECOND / 1000;
+            }
+            key.seen = now;
+            key.down = true;
+        }
+        _ => {
+            if let Some(other) = held.filter(|key| key.down) {
+                tet.apply(Action::Release(other.input));
+            }
+            press_key(tet, input);
+            *held = Some(HeldKey {
+                input,
+                pressed: now,
+                seen: now,
+                down: true,
+            });
+        }
+    }
+}
+
+fn press_key(tet: &mut TetrisBoard, input: Input) {
+    let before = tet.block_position;
+    tet.apply(Action::Press(input));
+    if before != tet.block_position {
+        debug_draw(tet);
+    }
+}
+
 pub fn quit() {
     println!("\x1b[?25h");
 }
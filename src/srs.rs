@@ -0,0 +1,101 @@
+use crate::block::TetrisBlocks;
+
+// スーパーローテーションシステム(SRS)の壁蹴りテーブル
+// 回転状態は 0:出現時, 1:R(右回り), 2:180度, 3:L(左回り)
+// オフセットは (x, y) で x は右が正, y は上が正 (盤面の y は下向きなので使うときに反転する)
+
+pub type Kick = (isize, isize);
+
+const JLSTZ_KICKS: [[Kick; 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 0 -> R
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 0
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 2
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 2 -> R
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 2 -> L
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 2
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 0
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 0 -> L
+];
+
+const I_KICKS: [[Kick; 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // 0 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // R -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // R -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // 2 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // 2 -> L
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // L -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // L -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // 0 -> L
+];
+
+// 180度回転には公式のテーブルがないので、よく使われるもの(TETR.IO の SRS+)を全ブロック共通で使う
+const HALF_TURN_KICKS: [[Kick; 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],     // 0 -> 2
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],       // R -> L
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)], // 2 -> 0
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],   // L -> R
+];
+
+const NO_KICKS: [Kick; 1] = [(0, 0)];
+
+fn transition_index(from: u8, to: u8) -> Option<usize> {
+    //! (from, to) の組をテーブルの行番号に変換する 隣り合う向き以外は None
+    match (from % 4, to % 4) {
+        (0, 1) => Some(0),
+        (1, 0) => Some(1),
+        (1, 2) => Some(2),
+        (2, 1) => Some(3),
+        (2, 3) => Some(4),
+        (3, 2) => Some(5),
+        (3, 0) => Some(6),
+        (0, 3) => Some(7),
+        _ => None,
+    }
+}
+
+pub fn kick_table(block: TetrisBlocks, from: u8, to: u8) -> &'static [Kick] {
+    //! ブロックと回転前後の向きから試すオフセットの一覧を返す
+    if block != TetrisBlocks::O && (from + 2) % 4 == to % 4 {
+        return &HALF_TURN_KICKS[(from % 4) as usize];
+    }
+    match (block, transition_index(from, to)) {
+        (TetrisBlocks::O, _) | (_, None) => &NO_KICKS,
+        (TetrisBlocks::I, Some(i)) => &I_KICKS[i],
+        (_, Some(i)) => &JLSTZ_KICKS[i],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guideline_first_kicks() {
+        assert_eq!(
+            kick_table(TetrisBlocks::T, 0, 1),
+            &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]
+        );
+        assert_eq!(
+            kick_table(TetrisBlocks::I, 0, 1),
+            &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]
+        );
+        assert_eq!(kick_table(TetrisBlocks::O, 0, 1), &[(0, 0)]);
+        assert_eq!(kick_table(TetrisBlocks::O, 0, 2), &[(0, 0)]);
+    }
+
+    #[test]
+    fn reverse_transition_negates_kicks() {
+        // 回して戻す壁蹴りは向きが逆になる テーブルの行の入れ違いを見つける
+        for block in [TetrisBlocks::T, TetrisBlocks::I] {
+            for from in 0..4u8 {
+                for to in [(from + 1) % 4, (from + 3) % 4] {
+                    let forward = kick_table(block, from, to);
+                    let back = kick_table(block, to, from);
+                    for (&(fx, fy), &(bx, by)) in forward.iter().zip(back) {
+                        assert_eq!((fx, fy), (-bx, -by), "{:?} {} -> {}", block, from, to);
+                    }
+                }
+            }
+        }
+    }
+}